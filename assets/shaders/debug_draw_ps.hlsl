@@ -0,0 +1,7 @@
+struct PsIn {
+    [[vk::location(0)]] float4 color: TEXCOORD0;
+};
+
+float4 main(PsIn ps): SV_TARGET {
+    return ps.color;
+}
@@ -0,0 +1,23 @@
+#include "inc/frame_constants.hlsl"
+#include "inc/uv.hlsl"
+
+[[vk::binding(0)]] Texture2D<float> depth_tex;
+[[vk::binding(1)]] RWTexture2D<float> output_tex;
+[[vk::binding(2)]] cbuffer _ {
+    float4 output_tex_size;
+};
+
+// Positive view-space distance along the camera axis; zero where there's no geometry.
+[numthreads(8, 8, 1)]
+void main(uint2 px: SV_DispatchThreadID) {
+    const float z_over_w = depth_tex[px];
+    if (z_over_w == 0.0) {
+        output_tex[px] = 0.0;
+        return;
+    }
+
+    const float2 uv = get_uv(px, output_tex_size);
+    const ViewRayContext view_ray_context = ViewRayContext::from_uv_and_depth(uv, z_over_w);
+
+    output_tex[px] = -view_ray_context.ray_hit_vs().z;
+}
@@ -1,11 +1,19 @@
 [[vk::binding(0)]] Texture2D<float4> main_tex;
 [[vk::binding(1)]] Texture2D<float4> gui_tex;
-[[vk::binding(2)]] RWTexture2D<float4> output_tex;
-[[vk::binding(3)]] cbuffer _ {
+[[vk::binding(2)]] Texture2D<float4> overlay_tex;
+[[vk::binding(3)]] RWTexture2D<float4> output_tex;
+[[vk::binding(4)]] cbuffer _ {
     float4 main_tex_size;
     float4 output_tex_size;
+    // xy: top-left corner, zw: size; in UV units of the output
+    float4 overlay_rect;
+    // If set, the output format applies the sRGB OETF itself, so we write linear values.
+    uint output_is_srgb;
+    // 0..1; zero disables sharpening
+    float sharpening;
 };
 
+#include "inc/samplers.hlsl"
 #include "inc/image.hlsl"
 #include "inc/color/srgb.hlsl"
 
@@ -20,6 +28,33 @@ struct LinearToSrgbRemap {
     }
 };
 
+float3 load_main_srgb(int2 px) {
+    px = clamp(px, 0, int2(main_tex_size.xy) - 1);
+    return sRGB_OETF(saturate(main_tex[px].rgb));
+}
+
+// Contrast-adaptive sharpening in the style of AMD FidelityFX CAS. Evaluated on the cross
+// around the nearest texel of `main_tex`, and returned as a delta to apply to the filtered value.
+float3 sharpening_delta(float2 uv) {
+    const int2 px = int2(uv * main_tex_size.xy);
+
+    const float3 c = load_main_srgb(px);
+    const float3 n = load_main_srgb(px + int2(0, -1));
+    const float3 s = load_main_srgb(px + int2(0, 1));
+    const float3 e = load_main_srgb(px + int2(1, 0));
+    const float3 w = load_main_srgb(px + int2(-1, 0));
+
+    const float3 mn = min(c, min(min(n, s), min(e, w)));
+    const float3 mx = max(c, max(max(n, s), max(e, w)));
+
+    // Back off where the neighborhood is close to clipping, to avoid ringing.
+    const float3 amp = sqrt(saturate(min(mn, 1.0 - mx) / max(mx, 1e-5)));
+    const float3 weight = amp * (-1.0 / lerp(8.0, 5.0, sharpening));
+
+    const float3 sharpened = (c + (n + s + e + w) * weight) / (1.0 + 4.0 * weight);
+    return sharpened - c;
+}
+
 [numthreads(8, 8, 1)]
 void main(in uint2 px : SV_DispatchThreadID) {
     #if 1
@@ -33,10 +68,27 @@ void main(in uint2 px : SV_DispatchThreadID) {
     } else {
         main = sRGB_OETF(saturate(main_tex[px].rgb));
     }
+
+    if (sharpening > 0.0) {
+        main = saturate(main + sharpening_delta((px + 0.5) / output_tex_size.xy));
+    }
+
+    const float2 overlay_uv = ((px + 0.5) * output_tex_size.zw - overlay_rect.xy) / overlay_rect.zw;
+    if (all(overlay_uv >= 0.0) && all(overlay_uv < 1.0)) {
+        const float4 overlay = overlay_tex.SampleLevel(sampler_llc, overlay_uv, 0);
+        main = lerp(main, overlay.rgb, overlay.a);
+    }
+
     float4 gui = gui_tex[px];
 
     float3 result = main.rgb * (1.0 - gui.a) + gui.rgb;
     //float3 result = lerp(main, gui.rgb, gui.a);
+
+    // Compositing happens in sRGB space; if the output format encodes on write,
+    // hand it linear values instead.
+    if (output_is_srgb) {
+        result = sRGB_EOTF(result);
+    }
     #else
     float3 result = float3(0.7, 0.4, 0.1);
     #endif
@@ -4,6 +4,10 @@
 #include "quasi_random.hlsl"
 #include "bindless_textures.hlsl"
 
+// Tileable 256x256 blue noise from the bindless texture table, available to all passes.
+// The tile is offset along the R2 sequence by `n`; pass `frame_constants.frame_index`
+// for noise which animates over frames for temporal accumulation.
+//
 // The source texture is RGBA8, and the output here is quantized to [0.5/256 .. 255.5/256]
 float4 blue_noise_for_pixel(uint2 px, uint n) {
     const uint2 tex_dims = uint2(256, 256);
@@ -20,10 +20,15 @@ static float2 SAMPLE_BEZOLD_BRUCKE_LUT(float coord) {
     float4 output_tex_size;
     float input_multiplier;
     float contrast;
+    uint tonemap_operator;
 };
 
+#define TONEMAP_DISPLAY_TRANSFORM 0
+#define TONEMAP_REINHARD 1
+#define TONEMAP_ACES 2
+#define TONEMAP_NONE 3
+
 #define USE_GRADE 0
-#define USE_DISPLAY_TRANSFORM 1
 #define USE_DITHER 1
 #define USE_SHARPEN 0
 #define USE_VIGNETTE 1
@@ -34,6 +39,16 @@ static const float sharpen_amount = 0.1;
 static const float glare_amount = 0.05;
 //static const float glare_amount = 0.0;
 
+// https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+float3 tonemap_aces_narkowicz(float3 x) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return saturate((x * (a * x + b)) / (x * (c * x + d) + e));
+}
+
 float sharpen_remap(float l) {
     return sqrt(l);
 }
@@ -164,10 +179,16 @@ void main(uint2 px: SV_DispatchThreadID, uint idx_within_group: SV_GroupIndex) {
     col = push_down_black_point(col, 0.2, 1.25);
 #endif
 
-#if USE_DISPLAY_TRANSFORM
-    // Apply a perceptually neutral display transform
-    col = display_transform_sRGB(col);
-#endif
+    if (tonemap_operator == TONEMAP_DISPLAY_TRANSFORM) {
+        // Apply a perceptually neutral display transform
+        col = display_transform_sRGB(col);
+    } else if (tonemap_operator == TONEMAP_REINHARD) {
+        col = col / (1.0 + col);
+    } else if (tonemap_operator == TONEMAP_ACES) {
+        col = tonemap_aces_narkowicz(col);
+    } else {
+        col = saturate(col);
+    }
 
     // Crank up the contrast
     col = pow(col, contrast);
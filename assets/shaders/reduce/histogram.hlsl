@@ -0,0 +1,21 @@
+[[vk::binding(0)]] Texture2D<float4> input_tex;
+[[vk::binding(1)]] RWStructuredBuffer<uint> output_buf;
+[[vk::binding(2)]] cbuffer _ {
+    uint2 input_extent;
+    uint bin_count;
+    float min_value;
+    float max_value;
+};
+
+// Values outside of `min_value..max_value` are clamped into the first and last bins.
+[numthreads(8, 8, 1)]
+void main(uint2 px: SV_DispatchThreadID) {
+    if (any(px >= input_extent)) {
+        return;
+    }
+
+    const float t = saturate((input_tex[px].x - min_value) / (max_value - min_value));
+    const uint bin = min(uint(t * bin_count), bin_count - 1);
+
+    InterlockedAdd(output_buf[bin], 1);
+}
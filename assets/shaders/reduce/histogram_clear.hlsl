@@ -0,0 +1,11 @@
+[[vk::binding(0)]] RWStructuredBuffer<uint> output_buf;
+[[vk::binding(1)]] cbuffer _ {
+    uint bin_count;
+};
+
+[numthreads(64, 1, 1)]
+void main(uint bin: SV_DispatchThreadID) {
+    if (bin < bin_count) {
+        output_buf[bin] = 0;
+    }
+}
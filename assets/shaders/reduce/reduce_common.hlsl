@@ -0,0 +1,82 @@
+#ifndef REDUCE_COMMON_HLSL
+#define REDUCE_COMMON_HLSL
+
+// Must match `ReduceOp` in `renderers/reduce.rs`
+#define REDUCE_OP_MIN 0
+#define REDUCE_OP_MAX 1
+#define REDUCE_OP_SUM 2
+
+#define REDUCE_GROUP_SIZE 64
+
+#ifndef REDUCE_USE_WAVE_OPS
+    #define REDUCE_USE_WAVE_OPS 0
+#endif
+
+float reduce_identity(uint op) {
+    switch (op) {
+        case REDUCE_OP_MIN: return asfloat(0x7f800000);  // +inf
+        case REDUCE_OP_MAX: return asfloat(0xff800000);  // -inf
+        default: return 0.0;
+    }
+}
+
+float reduce_combine(uint op, float a, float b) {
+    switch (op) {
+        case REDUCE_OP_MIN: return min(a, b);
+        case REDUCE_OP_MAX: return max(a, b);
+        default: return a + b;
+    }
+}
+
+groupshared float reduce_scratch[REDUCE_GROUP_SIZE];
+
+// Reduces one value per thread across the group; the result is valid in thread 0.
+float reduce_group(uint op, uint idx, float value) {
+    reduce_scratch[idx] = value;
+    GroupMemoryBarrierWithGroupSync();
+
+    for (uint stride = REDUCE_GROUP_SIZE / 2; stride > 0; stride >>= 1) {
+        if (idx < stride) {
+            reduce_scratch[idx] = reduce_combine(op, reduce_scratch[idx], reduce_scratch[idx + stride]);
+        }
+        GroupMemoryBarrierWithGroupSync();
+    }
+
+    return reduce_scratch[0];
+}
+
+float reduce_wave(uint op, float value) {
+    switch (op) {
+        case REDUCE_OP_MIN: return WaveActiveMin(value);
+        case REDUCE_OP_MAX: return WaveActiveMax(value);
+        default: return WaveActiveSum(value);
+    }
+}
+
+// Same as `reduce_group`, but reduces within waves first, leaving only
+// one value per wave to go through group shared memory.
+float reduce_group_wave(uint op, uint idx, float value) {
+    const uint lane_count = WaveGetLaneCount();
+    const uint wave_count = (REDUCE_GROUP_SIZE + lane_count - 1) / lane_count;
+
+    const float wave_result = reduce_wave(op, value);
+    if (WaveIsFirstLane()) {
+        reduce_scratch[idx / lane_count] = wave_result;
+    }
+    GroupMemoryBarrierWithGroupSync();
+
+    float result = reduce_scratch[0];
+    for (uint i = 1; i < wave_count; ++i) {
+        result = reduce_combine(op, result, reduce_scratch[i]);
+    }
+
+    return result;
+}
+
+#if REDUCE_USE_WAVE_OPS
+    #define REDUCE_GROUP reduce_group_wave
+#else
+    #define REDUCE_GROUP reduce_group
+#endif
+
+#endif  // REDUCE_COMMON_HLSL
@@ -0,0 +1,24 @@
+#include "reduce_common.hlsl"
+
+[[vk::binding(0)]] Texture2D<float4> input_tex;
+[[vk::binding(1)]] RWStructuredBuffer<float> partials_buf;
+[[vk::binding(2)]] cbuffer _ {
+    uint2 input_extent;
+    uint op;
+    uint group_count_x;
+};
+
+// First stage: one partial result per 8x8 tile of the input.
+[numthreads(8, 8, 1)]
+void main(uint2 px: SV_DispatchThreadID, uint idx_within_group: SV_GroupIndex, uint2 group_id: SV_GroupID) {
+    float value = reduce_identity(op);
+    if (all(px < input_extent)) {
+        value = input_tex[px].x;
+    }
+
+    const float result = REDUCE_GROUP(op, idx_within_group, value);
+
+    if (0 == idx_within_group) {
+        partials_buf[group_id.y * group_count_x + group_id.x] = result;
+    }
+}
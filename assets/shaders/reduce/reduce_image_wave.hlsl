@@ -0,0 +1,2 @@
+#define REDUCE_USE_WAVE_OPS 1
+#include "reduce_image.hlsl"
@@ -0,0 +1,23 @@
+#include "reduce_common.hlsl"
+
+[[vk::binding(0)]] StructuredBuffer<float> partials_buf;
+[[vk::binding(1)]] RWStructuredBuffer<float> output_buf;
+[[vk::binding(2)]] cbuffer _ {
+    uint partial_count;
+    uint op;
+};
+
+// Second stage: a single group folds all the partials into `output_buf[0]`.
+[numthreads(REDUCE_GROUP_SIZE, 1, 1)]
+void main(uint idx: SV_GroupIndex) {
+    float value = reduce_identity(op);
+    for (uint i = idx; i < partial_count; i += REDUCE_GROUP_SIZE) {
+        value = reduce_combine(op, value, partials_buf[i]);
+    }
+
+    const float result = REDUCE_GROUP(op, idx, value);
+
+    if (0 == idx) {
+        output_buf[0] = result;
+    }
+}
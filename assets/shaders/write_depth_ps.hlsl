@@ -0,0 +1,19 @@
+#include "inc/frame_constants.hlsl"
+
+[[vk::binding(0)]] Texture2D<float> linear_depth_tex;
+
+struct PsIn {
+	float4 position: SV_Position;
+};
+
+float main(PsIn ps): SV_Depth {
+    const float linear_depth = linear_depth_tex[uint2(ps.position.xy)];
+    if (linear_depth == 0.0) {
+        discard;
+    }
+
+    // Project back into clip space; for the reverse-Z projection, nearer surfaces
+    // end up with larger values.
+    const float4 cs_pos = mul(frame_constants.view_constants.view_to_clip, float4(0.0, 0.0, -linear_depth, 1.0));
+    return cs_pos.z / cs_pos.w;
+}
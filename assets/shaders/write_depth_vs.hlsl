@@ -0,0 +1,12 @@
+struct VsOut {
+	float4 position: SV_Position;
+};
+
+// Full-screen triangle
+VsOut main(uint vid: SV_VertexID) {
+    const float2 uv = float2((vid << 1) & 2, vid & 2);
+
+    VsOut vsout;
+    vsout.position = float4(uv * 2.0 - 1.0, 0.0, 1.0);
+    return vsout;
+}
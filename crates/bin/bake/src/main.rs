@@ -12,6 +12,14 @@ struct Opt {
     #[structopt(long, default_value = "1.0")]
     scale: f32,
 
+    /// The scene is authored with +Z up rather than +Y.
+    #[structopt(long)]
+    z_up: bool,
+
+    /// The scene is authored in a left-handed coordinate system.
+    #[structopt(long)]
+    left_handed: bool,
+
     #[structopt(short = "o")]
     output_name: String,
 }
@@ -25,5 +33,13 @@ fn main() -> Result<()> {
         path: opt.scene,
         output_name: opt.output_name,
         scale: opt.scale,
+        coordinate_system: CoordinateSystem {
+            handedness: if opt.left_handed {
+                Handedness::Left
+            } else {
+                Handedness::Right
+            },
+            up: if opt.z_up { UpAxis::Z } else { UpAxis::Y },
+        },
     })
 }
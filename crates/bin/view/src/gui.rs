@@ -122,6 +122,24 @@ impl RuntimeState {
                         .range(0..=10)
                         .build(ui, &mut state.lights.count);*/
 
+                    ui.text("Quality preset");
+                    for (label, preset) in [
+                        (im_str!("Low"), QualityPreset::Low),
+                        (im_str!("Medium"), QualityPreset::Medium),
+                        (im_str!("High"), QualityPreset::High),
+                        (im_str!("Ultra"), QualityPreset::Ultra),
+                    ] {
+                        ui.same_line(0.0);
+                        if ui.button(label, [0.0, 0.0]) {
+                            ctx.world_renderer.apply_quality_preset(preset);
+                        }
+                    }
+
+                    ui.checkbox(
+                        im_str!("Motion blur"),
+                        &mut ctx.world_renderer.use_motion_blur,
+                    );
+
                     ui.checkbox(
                         im_str!("Scroll irradiance cache"),
                         &mut ctx.world_renderer.ircache.enable_scroll,
@@ -408,6 +426,10 @@ impl RuntimeState {
                     ui.checkbox(im_str!("Allow pass overlap"), unsafe {
                         &mut kajiya::rg::RG_ALLOW_PASS_OVERLAP
                     });
+
+                    ui.checkbox(im_str!("Trace render graph execution"), unsafe {
+                        &mut kajiya::rg::RG_TRACE_EXECUTION
+                    });
                 }
 
                 if imgui::CollapsingHeader::new(im_str!("GPU passes"))
@@ -6,10 +6,7 @@ mod runtime;
 mod scene;
 mod sequence;
 
-use std::{
-    fs::File,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 use kajiya_simple::*;
 use opt::*;
@@ -41,7 +38,7 @@ impl AppState {
                     .with_decorations(!opt.no_window_decorations),
             )?;
 
-        let runtime = RuntimeState::new(&mut persisted, &mut kajiya.world_renderer, opt);
+        let runtime = RuntimeState::new(&mut persisted, &mut kajiya.world_renderer, opt)?;
 
         Ok(Self {
             persisted,
@@ -91,10 +88,16 @@ fn main() -> anyhow::Result<()> {
 
     let opt = Opt::from_args();
 
-    let mut persisted: PersistedState = File::open(APP_STATE_CONFIG_FILE_PATH)
-        .map_err(|err| anyhow::anyhow!(err))
-        .and_then(|file| Ok(ron::de::from_reader(file)?))
-        .unwrap_or_default();
+    let state_path = opt
+        .state
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(APP_STATE_CONFIG_FILE_PATH));
+
+    let mut persisted = if state_path.exists() {
+        PersistedState::load(&state_path)?
+    } else {
+        PersistedState::default()
+    };
 
     // If supplying a new scene, clear the previous one.
     if opt.scene.is_some() || opt.mesh.is_some() {
@@ -111,11 +114,7 @@ fn main() -> anyhow::Result<()> {
 
     let state = state.run()?;
 
-    ron::ser::to_writer_pretty(
-        File::create(APP_STATE_CONFIG_FILE_PATH)?,
-        &state,
-        Default::default(),
-    )?;
+    state.save(&state_path)?;
 
     Ok(())
 }
@@ -14,6 +14,11 @@ pub struct Opt {
     #[structopt(long, default_value = "1.0")]
     pub temporal_upsampling: f32,
 
+    /// Session state (camera, lighting, exposure, sequence, scene) to load on start
+    /// and save on exit. Defaults to `view_state.ron`.
+    #[structopt(long)]
+    pub state: Option<PathBuf>,
+
     #[structopt(long)]
     pub scene: Option<PathBuf>,
 
@@ -37,4 +42,12 @@ pub struct Opt {
 
     #[structopt(long)]
     pub physical_device_index: Option<usize>,
+
+    /// Write per-frame keyboard and mouse state to this file
+    #[structopt(long)]
+    pub record_input: Option<PathBuf>,
+
+    /// Replay keyboard and mouse state from a file written with `--record-input`
+    #[structopt(long, conflicts_with = "record-input")]
+    pub replay_input: Option<PathBuf>,
 }
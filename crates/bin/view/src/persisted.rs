@@ -1,4 +1,9 @@
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
 
 use kajiya::world_renderer::InstanceHandle;
 use kajiya_simple::{Affine3A, EulerRot, Mat2, Quat, Vec2, Vec3, Vec3Swizzles};
@@ -282,7 +287,7 @@ impl SceneElementTransform {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
 pub enum MeshSource {
     File(PathBuf),
     Cache(PathBuf),
@@ -331,3 +336,56 @@ impl ShouldResetPathTracer for PersistedState {
             || self.scene.should_reset_path_tracer(&other.scene)
     }
 }
+
+/// Version of the session file format written by `PersistedState::save`.
+/// Bump when a change to `PersistedState` can't be read by older builds.
+pub const PERSISTED_STATE_VERSION: u32 = 1;
+
+#[derive(serde::Deserialize)]
+struct PersistedStateHeader {
+    // Files written before versioning have no header, and parse as version 0.
+    #[serde(default)]
+    version: u32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct VersionedPersistedState {
+    version: u32,
+    state: PersistedState,
+}
+
+impl PersistedState {
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let versioned = VersionedPersistedState {
+            version: PERSISTED_STATE_VERSION,
+            state: self.clone(),
+        };
+
+        ron::ser::to_writer_pretty(File::create(path)?, &versioned, Default::default())
+            .with_context(|| format!("Writing session state to {:?}", path))
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading session state from {:?}", path))?;
+
+        let header: PersistedStateHeader = ron::de::from_str(&text)
+            .with_context(|| format!("Parsing the session state header of {:?}", path))?;
+
+        match header.version {
+            0 => ron::de::from_str(&text)
+                .with_context(|| format!("Parsing unversioned session state {:?}", path)),
+            PERSISTED_STATE_VERSION => {
+                let versioned: VersionedPersistedState = ron::de::from_str(&text)
+                    .with_context(|| format!("Parsing session state {:?}", path))?;
+                Ok(versioned.state)
+            }
+            version => anyhow::bail!(
+                "Session state {:?} has format version {}, but this build only reads versions up to {}",
+                path,
+                version,
+                PERSISTED_STATE_VERSION
+            ),
+        }
+    }
+}
@@ -17,15 +17,13 @@ use crate::{
     PersistedState,
 };
 
-use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    fs::File,
-    hash::{Hash, Hasher},
-    path::PathBuf,
-};
+use std::{collections::HashMap, fs::File, path::PathBuf};
 
 pub const MAX_FPS_LIMIT: u32 = 256;
 
+/// How far away the far plane of the game camera's frustum is drawn while the editor camera is detached.
+const GAME_CAMERA_FRUSTUM_DISTANCE: f32 = 5.0;
+
 pub struct RuntimeState {
     pub camera: CameraRig,
     pub mouse: MouseState,
@@ -43,10 +41,17 @@ pub struct RuntimeState {
     pub reset_path_tracer: bool,
 
     pub active_camera_key: Option<usize>,
+
+    /// Position and rotation of the game camera while a free-fly editor camera is detached from it.
+    /// The game camera's frustum is drawn in the debug view, and the rig drives the editor camera.
+    detached_game_camera: Option<(Vec3, Quat)>,
     sequence_playback_state: SequencePlaybackState,
     pub sequence_playback_speed: f32,
 
-    known_meshes: HashMap<PathBuf, MeshHandle>,
+    known_meshes: HashMap<MeshSource, MeshHandle>,
+
+    input_recorder: Option<InputRecorder>,
+    input_playback: Option<InputPlayback>,
 }
 
 enum SequencePlaybackState {
@@ -61,8 +66,8 @@ impl RuntimeState {
     pub fn new(
         persisted: &mut PersistedState,
         world_renderer: &mut WorldRenderer,
-        _opt: &Opt,
-    ) -> Self {
+        opt: &Opt,
+    ) -> anyhow::Result<Self> {
         let camera: CameraRig = CameraRig::builder()
             .with(Position::new(persisted.camera.position))
             .with(YawPitch::new().rotation_quat(persisted.camera.rotation))
@@ -112,10 +117,22 @@ impl RuntimeState {
             reset_path_tracer: false,
 
             active_camera_key: None,
+            detached_game_camera: None,
             sequence_playback_state: SequencePlaybackState::NotPlaying,
             sequence_playback_speed: 1.0,
 
             known_meshes: Default::default(),
+
+            input_recorder: opt
+                .record_input
+                .as_ref()
+                .map(InputRecorder::create)
+                .transpose()?,
+            input_playback: opt
+                .replay_input
+                .as_ref()
+                .map(InputPlayback::open)
+                .transpose()?,
         };
 
         // Load meshes that the persisted scene was referring to
@@ -140,7 +157,7 @@ impl RuntimeState {
             }
         }
 
-        res
+        Ok(res)
     }
 
     pub fn clear_scene(
@@ -274,10 +291,27 @@ impl RuntimeState {
             }
         }
 
+        if self.keyboard.was_just_pressed(VirtualKeyCode::G) {
+            if let Some((position, rotation)) = self.detached_game_camera.take() {
+                // Snap the rig back to the game camera.
+                self.camera.driver_mut::<Position>().position = position;
+                self.camera
+                    .driver_mut::<YawPitch>()
+                    .set_rotation_quat(rotation);
+                self.camera.update(1e10);
+            } else {
+                self.detached_game_camera =
+                    Some((persisted.camera.position, persisted.camera.rotation));
+            }
+        }
+
         self.camera.update(ctx.dt_filtered);
 
-        persisted.camera.position = self.camera.final_transform.position;
-        persisted.camera.rotation = self.camera.final_transform.rotation;
+        // While the editor camera is detached, the persisted (game) camera stays put.
+        if self.detached_game_camera.is_none() {
+            persisted.camera.position = self.camera.final_transform.position;
+            persisted.camera.rotation = self.camera.final_transform.rotation;
+        }
 
         if self.keyboard.was_just_pressed(VirtualKeyCode::C) {
             println!(
@@ -411,6 +445,33 @@ impl RuntimeState {
         }
     }
 
+    fn record_or_replay_input(&mut self, ctx: &mut FrameContext) {
+        if let Some(recorder) = &mut self.input_recorder {
+            if let Err(err) = recorder.record(ctx.dt_filtered, &self.keyboard, &self.mouse) {
+                log::error!("Failed to record input: {:#}", err);
+                self.input_recorder = None;
+            }
+        }
+
+        if let Some(playback) = &mut self.input_playback {
+            match playback.next_frame() {
+                Ok(Some(frame)) => {
+                    ctx.dt_filtered = frame.dt;
+                    self.keyboard = frame.keyboard;
+                    self.mouse = frame.mouse;
+                }
+                Ok(None) => {
+                    log::info!("Input replay finished");
+                    self.input_playback = None;
+                }
+                Err(err) => {
+                    log::error!("Failed to replay input: {:#}", err);
+                    self.input_playback = None;
+                }
+            }
+        }
+    }
+
     pub fn frame(
         &mut self,
         mut ctx: FrameContext,
@@ -425,6 +486,7 @@ impl RuntimeState {
 
         self.keyboard.update(ctx.events);
         self.mouse.update(ctx.events);
+        self.record_or_replay_input(&mut ctx);
         self.handle_file_drop_events(persisted, ctx.world_renderer, ctx.events);
 
         let orig_persisted_state = persisted.clone();
@@ -454,6 +516,11 @@ impl RuntimeState {
             };
         }
 
+        if self.keyboard.was_just_pressed(VirtualKeyCode::Pause) {
+            let paused = !ctx.world_renderer.is_paused();
+            ctx.world_renderer.set_paused(paused);
+        }
+
         ctx.world_renderer.ev_shift = persisted.exposure.ev_shift;
         ctx.world_renderer.contrast = persisted.exposure.contrast;
         ctx.world_renderer.dynamic_exposure.enabled = persisted.exposure.use_dynamic_adaptation;
@@ -484,6 +551,14 @@ impl RuntimeState {
             ..Default::default()
         };
 
+        if let Some(game_camera) = self.detached_game_camera {
+            ctx.world_renderer.debug_draw.frustum(
+                &game_camera.through(&lens),
+                GAME_CAMERA_FRUSTUM_DISTANCE,
+                Vec3::new(1.0, 0.8, 0.1),
+            );
+        }
+
         WorldFrameDesc {
             camera_matrices: self
                 .camera
@@ -593,44 +668,25 @@ impl RuntimeState {
         world_renderer: &mut WorldRenderer,
         source: &MeshSource,
     ) -> anyhow::Result<MeshHandle> {
-        log::info!("Loading a mesh from {:?}", source);
-
-        let path = match source {
-            MeshSource::File(path) => {
-                fn calculate_hash(t: &PathBuf) -> u64 {
-                    let mut s = DefaultHasher::new();
-                    t.hash(&mut s);
-                    s.finish()
-                }
+        if let Some(mesh) = self.known_meshes.get(source) {
+            return Ok(*mesh);
+        }
 
-                let path_hash = match path.canonicalize() {
-                    Ok(canonical) => calculate_hash(&canonical),
-                    Err(_) => calculate_hash(path),
-                };
-
-                let cached_mesh_name = format!("{:8.8x}", path_hash);
-                let cached_mesh_path = PathBuf::from(format!("/cache/{}.mesh", cached_mesh_name));
-
-                if !canonical_path_from_vfs(&cached_mesh_path).map_or(false, |path| path.exists()) {
-                    kajiya_asset_pipe::process_mesh_asset(
-                        kajiya_asset_pipe::MeshAssetProcessParams {
-                            path: path.clone(),
-                            output_name: cached_mesh_name,
-                            scale: 1.0,
-                        },
-                    )?;
-                }
+        log::info!("Loading a mesh from {:?}", source);
 
-                cached_mesh_path
+        let mesh = match source {
+            MeshSource::File(path) => world_renderer.load_gltf(
+                path,
+                CoordinateSystem::default(),
+                AddMeshOptions::new(),
+            )?,
+            MeshSource::Cache(path) => {
+                world_renderer.add_baked_mesh(path, AddMeshOptions::new())?
             }
-            MeshSource::Cache(path) => path.clone(),
         };
 
-        Ok(*self.known_meshes.entry(path.clone()).or_insert_with(|| {
-            world_renderer
-                .add_baked_mesh(path, AddMeshOptions::new())
-                .unwrap()
-        }))
+        self.known_meshes.insert(source.clone(), mesh);
+        Ok(mesh)
     }
 
     pub(crate) fn add_mesh_instance(
@@ -10,10 +10,15 @@ use turbosloth::*;
 
 use anyhow::Result;
 
+pub use rust_shaders_shared::coordinate_system::{CoordinateSystem, Handedness, UpAxis};
+
 pub struct MeshAssetProcessParams {
     pub path: PathBuf,
     pub output_name: String,
     pub scale: f32,
+
+    /// Convention the scene is authored in; see `LoadGltfScene::coordinate_system`.
+    pub coordinate_system: CoordinateSystem,
 }
 
 pub fn process_mesh_asset(opt: MeshAssetProcessParams) -> Result<()> {
@@ -29,6 +34,7 @@ pub fn process_mesh_asset(opt: MeshAssetProcessParams) -> Result<()> {
             scale: opt.scale,
             //rotation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
             rotation: Quat::IDENTITY,
+            coordinate_system: opt.coordinate_system,
         }
         .into_lazy();
 
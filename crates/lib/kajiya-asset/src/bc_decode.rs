@@ -0,0 +1,559 @@
+//! CPU decoding of block-compressed images, for devices which can't sample them.
+
+use kajiya_backend::ash::vk;
+
+/// Decodes the mips of a BC1, BC3, BC5, or BC7 image into RGBA8, returning the new format.
+/// BC5 decodes into the red and green channels, with blue at zero and alpha at one.
+pub fn decode_to_rgba8(
+    format: vk::Format,
+    extent: [u32; 2],
+    mips: &[&[u8]],
+) -> anyhow::Result<(vk::Format, Vec<Vec<u8>>)> {
+    let (decode_block, block_bytes, rgba8_format): (fn(&[u8]) -> [[u8; 4]; 16], usize, _) =
+        match format {
+            vk::Format::BC1_RGB_UNORM_BLOCK => (decode_bc1_block, 8, vk::Format::R8G8B8A8_UNORM),
+            vk::Format::BC1_RGB_SRGB_BLOCK => (decode_bc1_block, 8, vk::Format::R8G8B8A8_SRGB),
+            vk::Format::BC3_UNORM_BLOCK => (decode_bc3_block, 16, vk::Format::R8G8B8A8_UNORM),
+            vk::Format::BC3_SRGB_BLOCK => (decode_bc3_block, 16, vk::Format::R8G8B8A8_SRGB),
+            vk::Format::BC5_UNORM_BLOCK => (decode_bc5_unorm_block, 16, vk::Format::R8G8B8A8_UNORM),
+            vk::Format::BC5_SNORM_BLOCK => (decode_bc5_snorm_block, 16, vk::Format::R8G8B8A8_SNORM),
+            vk::Format::BC7_UNORM_BLOCK => (decode_bc7_block, 16, vk::Format::R8G8B8A8_UNORM),
+            vk::Format::BC7_SRGB_BLOCK => (decode_bc7_block, 16, vk::Format::R8G8B8A8_SRGB),
+            _ => anyhow::bail!("Decoding {:?} on the CPU is not supported", format),
+        };
+
+    let mips = mips
+        .iter()
+        .enumerate()
+        .map(|(mip_level, mip)| {
+            let width = (extent[0] >> mip_level).max(1) as usize;
+            let height = (extent[1] >> mip_level).max(1) as usize;
+            let blocks_x = (width + 3) / 4;
+            let blocks_y = (height + 3) / 4;
+
+            anyhow::ensure!(
+                mip.len() >= blocks_x * blocks_y * block_bytes,
+                "Mip {} of a {:?} image is too small",
+                mip_level,
+                format
+            );
+
+            let mut rgba = vec![0u8; width * height * 4];
+            for (block_idx, block) in mip
+                .chunks_exact(block_bytes)
+                .take(blocks_x * blocks_y)
+                .enumerate()
+            {
+                let texels = decode_block(block);
+                let (bx, by) = (block_idx % blocks_x * 4, block_idx / blocks_x * 4);
+
+                for (texel_idx, texel) in texels.iter().enumerate() {
+                    let (x, y) = (bx + texel_idx % 4, by + texel_idx / 4);
+                    if x < width && y < height {
+                        let offset = (y * width + x) * 4;
+                        rgba[offset..offset + 4].copy_from_slice(texel);
+                    }
+                }
+            }
+
+            Ok(rgba)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok((rgba8_format, mips))
+}
+
+fn rgb565_to_rgb8(c: u16) -> [u32; 3] {
+    let r = (c >> 11) as u32 & 31;
+    let g = (c >> 5) as u32 & 63;
+    let b = c as u32 & 31;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+// Color endpoints and 2-bit indices. With `allow_punch_through`, blocks with
+// `c0 <= c1` use three colors and black, as in BC1. The alpha of the black is
+// dropped, as only the RGB variants of BC1 are used.
+fn decode_color_block(block: &[u8], allow_punch_through: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (e0, e1) = (rgb565_to_rgb8(c0), rgb565_to_rgb8(c1));
+
+    let mix = |w0: u32, w1: u32| {
+        let channel = |i: usize| ((e0[i] * w0 + e1[i] * w1) / (w0 + w1)) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+
+    let palette = if c0 > c1 || !allow_punch_through {
+        [mix(1, 0), mix(0, 1), mix(2, 1), mix(1, 2)]
+    } else {
+        [mix(1, 0), mix(0, 1), mix(1, 1), [0, 0, 0, 255]]
+    };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut texels = [[0u8; 4]; 16];
+    for (i, texel) in texels.iter_mut().enumerate() {
+        *texel = palette[(indices >> (i * 2)) as usize & 3];
+    }
+    texels
+}
+
+// Two 8-bit endpoints and 3-bit indices, as used for BC3 alpha and BC4/BC5 channels.
+// Signed values are returned as their two's complement bytes.
+fn decode_bc4_block(block: &[u8], signed: bool) -> [u8; 16] {
+    // -128 and -127 both decode to -1.0
+    let endpoint = |b: u8| {
+        if signed {
+            (b as i8).max(-127) as i32
+        } else {
+            b as i32
+        }
+    };
+    let (a0, a1) = (endpoint(block[0]), endpoint(block[1]));
+    let (min, max) = if signed { (-127, 127) } else { (0, 255) };
+
+    let mut palette = [0i32; 8];
+    palette[0] = a0;
+    palette[1] = a1;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = ((7 - i as i32) * a0 + i as i32 * a1) / 7;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = ((5 - i as i32) * a0 + i as i32 * a1) / 5;
+        }
+        palette[6] = min;
+        palette[7] = max;
+    }
+
+    let mut index_bytes = [0u8; 8];
+    index_bytes[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(index_bytes);
+
+    let mut values = [0u8; 16];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[(indices >> (i * 3)) as usize & 7] as u8;
+    }
+    values
+}
+
+fn decode_bc1_block(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_color_block(block, true)
+}
+
+fn decode_bc3_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let alpha = decode_bc4_block(&block[0..8], false);
+    let mut texels = decode_color_block(&block[8..16], false);
+    for (texel, alpha) in texels.iter_mut().zip(alpha.iter()) {
+        texel[3] = *alpha;
+    }
+    texels
+}
+
+fn decode_bc5_block(block: &[u8], signed: bool) -> [[u8; 4]; 16] {
+    let red = decode_bc4_block(&block[0..8], signed);
+    let green = decode_bc4_block(&block[8..16], signed);
+    let one = if signed { 127 } else { 255 };
+
+    let mut texels = [[0u8; 4]; 16];
+    for (i, texel) in texels.iter_mut().enumerate() {
+        *texel = [red[i], green[i], 0, one];
+    }
+    texels
+}
+
+fn decode_bc5_unorm_block(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_bc5_block(block, false)
+}
+
+fn decode_bc5_snorm_block(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_bc5_block(block, true)
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index2_bits: u32,
+}
+
+const fn bc7_mode(
+    subsets: usize,
+    [partition_bits, rotation_bits, index_selection_bits, color_bits, alpha_bits]: [u32; 5],
+    [endpoint_pbits, shared_pbits]: [bool; 2],
+    [index_bits, index2_bits]: [u32; 2],
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_pbits,
+        shared_pbits,
+        index_bits,
+        index2_bits,
+    }
+}
+
+// Indexed by the mode number, which is the number of zero bits before the first set one.
+const BC7_MODES: [Bc7Mode; 8] = [
+    bc7_mode(3, [4, 0, 0, 4, 0], [true, false], [3, 0]),
+    bc7_mode(2, [6, 0, 0, 6, 0], [false, true], [3, 0]),
+    bc7_mode(3, [6, 0, 0, 5, 0], [false, false], [2, 0]),
+    bc7_mode(2, [6, 0, 0, 7, 0], [true, false], [2, 0]),
+    bc7_mode(1, [0, 2, 1, 5, 6], [false, false], [2, 3]),
+    bc7_mode(1, [0, 2, 0, 7, 8], [false, false], [2, 2]),
+    bc7_mode(1, [0, 0, 0, 7, 7], [true, false], [4, 0]),
+    bc7_mode(2, [6, 0, 0, 5, 5], [true, false], [2, 0]),
+];
+
+// Subset of each texel, one bit per texel.
+const BC7_PARTITIONS_2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800,
+    0xFFE8, 0xFF00, 0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+    0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C, 0xAAAA, 0xF0F0, 0x5A5A, 0x33CC,
+    0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718,
+    0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+// Subset of each texel, two bits per texel.
+const BC7_PARTITIONS_3: [u32; 64] = [
+    0xAA685050, 0x6A5A5040, 0x5A5A4200, 0x5450A0A8, 0xA5A50000, 0xA0A05050, 0x5555A0A0, 0x5A5A5050,
+    0xAA550000, 0xAA555500, 0xAAAA5500, 0x90909090, 0x94949494, 0xA4A4A4A4, 0xA9A59450, 0x2A0A4250,
+    0xA5945040, 0x0A425054, 0xA5A5A500, 0x55A0A0A0, 0xA8A85454, 0x6A6A4040, 0xA4A45000, 0x1A1A0500,
+    0x0050A4A4, 0xAAA59090, 0x14696914, 0x69691400, 0xA08585A0, 0xAA821414, 0x50A4A450, 0x6A5A0200,
+    0xA9A58000, 0x5090A0A8, 0xA8A09050, 0x24242424, 0x00AA5500, 0x24924924, 0x24499224, 0x50A50A50,
+    0x500AA550, 0xAAAA4444, 0x66660000, 0xA5A0A5A0, 0x50A050A0, 0x69286928, 0x44AAAA44, 0x66666600,
+    0xAA444444, 0x54A854A8, 0x95809580, 0x96969600, 0xA85454A8, 0x80959580, 0xAA141414, 0x96960000,
+    0xAAAA1414, 0xA05050A0, 0xA0A5A5A0, 0x96000000, 0x40804080, 0xA9A8A9A8, 0xAAAAAA44, 0x2A4A5254,
+];
+
+// Texels whose index has its top bit implied to be zero, for subsets other than the first,
+// whose anchor is always texel 0.
+const BC7_ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+const BC7_ANCHORS_3_SECOND: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5,
+    15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5,
+    10, 8, 13, 15, 12, 3, 3,
+];
+
+const BC7_ANCHORS_3_THIRD: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6,
+    10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+const BC7_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn bc7_subset(subsets: usize, partition: usize, texel: usize) -> usize {
+    match subsets {
+        1 => 0,
+        2 => (BC7_PARTITIONS_2[partition] >> texel) as usize & 1,
+        _ => (BC7_PARTITIONS_3[partition] >> (texel * 2)) as usize & 3,
+    }
+}
+
+fn bc7_weights(index_bits: u32) -> &'static [u32] {
+    match index_bits {
+        2 => &BC7_WEIGHTS_2,
+        3 => &BC7_WEIGHTS_3,
+        _ => &BC7_WEIGHTS_4,
+    }
+}
+
+// Replicates the top bits of a `bits`-wide value into the low ones.
+fn bc7_expand(value: u32, bits: u32) -> u32 {
+    let value = value << (8 - bits);
+    value | (value >> bits)
+}
+
+// Reads fields from the least significant bit of the block up.
+struct BlockBits {
+    bits: u128,
+    pos: u32,
+}
+
+impl BlockBits {
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.pos) as u32 & ((1 << count) - 1);
+        self.pos += count;
+        value
+    }
+}
+
+fn decode_bc7_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let mode_idx = block[0].trailing_zeros() as usize;
+
+    // Reserved modes decode to transparent black.
+    let mode = match BC7_MODES.get(mode_idx) {
+        Some(mode) => mode,
+        None => return [[0u8; 4]; 16],
+    };
+
+    let mut bits = BlockBits {
+        bits: u128::from_le_bytes(block[0..16].try_into().unwrap()),
+        pos: mode_idx as u32 + 1,
+    };
+
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    // All reds, then all greens, blues, and alphas; two endpoints per subset.
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = bits.read(mode.color_bits);
+        }
+    }
+    for endpoint in &mut endpoints[..endpoint_count] {
+        endpoint[3] = bits.read(mode.alpha_bits);
+    }
+
+    let has_pbits = mode.endpoint_pbits || mode.shared_pbits;
+    let apply_pbit = |endpoint: &mut [u32; 4], pbit: u32| {
+        let channel_count = if mode.alpha_bits > 0 { 4 } else { 3 };
+        for value in &mut endpoint[..channel_count] {
+            *value = (*value << 1) | pbit;
+        }
+    };
+    if mode.endpoint_pbits {
+        for endpoint in &mut endpoints[..endpoint_count] {
+            apply_pbit(endpoint, bits.read(1));
+        }
+    }
+    if mode.shared_pbits {
+        for subset in endpoints[..endpoint_count].chunks_mut(2) {
+            let pbit = bits.read(1);
+            for endpoint in subset {
+                apply_pbit(endpoint, pbit);
+            }
+        }
+    }
+
+    for endpoint in &mut endpoints[..endpoint_count] {
+        for value in &mut endpoint[..3] {
+            *value = bc7_expand(*value, mode.color_bits + has_pbits as u32);
+        }
+        endpoint[3] = if mode.alpha_bits > 0 {
+            bc7_expand(endpoint[3], mode.alpha_bits + has_pbits as u32)
+        } else {
+            255
+        };
+    }
+
+    let anchors = match mode.subsets {
+        1 => [0, 0, 0],
+        2 => [0, BC7_ANCHORS_2[partition] as usize, 0],
+        _ => [
+            0,
+            BC7_ANCHORS_3_SECOND[partition] as usize,
+            BC7_ANCHORS_3_THIRD[partition] as usize,
+        ],
+    };
+
+    let mut indices = [0u32; 16];
+    for (texel, index) in indices.iter_mut().enumerate() {
+        let is_anchor = anchors[..mode.subsets].contains(&texel);
+        *index = bits.read(mode.index_bits - is_anchor as u32);
+    }
+
+    let mut indices2 = [0u32; 16];
+    if mode.index2_bits > 0 {
+        for (texel, index) in indices2.iter_mut().enumerate() {
+            *index = bits.read(mode.index2_bits - (texel == 0) as u32);
+        }
+    }
+
+    let interpolate = |e0: u32, e1: u32, weight: u32| ((64 - weight) * e0 + weight * e1 + 32) >> 6;
+
+    let mut texels = [[0u8; 4]; 16];
+    for (texel_idx, texel) in texels.iter_mut().enumerate() {
+        let subset = bc7_subset(mode.subsets, partition, texel_idx);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        // Modes 4 and 5 have separate indices for color and alpha; in mode 4,
+        // the index selection bit swaps which is which.
+        let (color_weight, alpha_weight) = if mode.index2_bits == 0 {
+            let weight = bc7_weights(mode.index_bits)[indices[texel_idx] as usize];
+            (weight, weight)
+        } else {
+            let primary = bc7_weights(mode.index_bits)[indices[texel_idx] as usize];
+            let secondary = bc7_weights(mode.index2_bits)[indices2[texel_idx] as usize];
+            if index_selection == 0 {
+                (primary, secondary)
+            } else {
+                (secondary, primary)
+            }
+        };
+
+        for channel in 0..3 {
+            texel[channel] = interpolate(e0[channel], e1[channel], color_weight) as u8;
+        }
+        texel[3] = interpolate(e0[3], e1[3], alpha_weight) as u8;
+
+        match rotation {
+            1 => texel.swap(0, 3),
+            2 => texel.swap(1, 3),
+            3 => texel.swap(2, 3),
+            _ => {}
+        }
+    }
+    texels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Packs `(value, bit_count)` fields from the least significant bit up.
+    fn pack_bits(fields: &[(u32, u32)]) -> [u8; 16] {
+        let mut bits = 0u128;
+        let mut pos = 0;
+        for &(value, count) in fields {
+            bits |= (value as u128) << pos;
+            pos += count;
+        }
+        assert!(pos <= 128);
+        bits.to_le_bytes()
+    }
+
+    #[test]
+    fn bc1_opaque_and_punch_through() {
+        // Red and blue endpoints; texel 0 picks c0, texel 1 picks the 2/3 c0 + 1/3 c1 color.
+        let block = pack_bits(&[(0xF800, 16), (0x001F, 16), (0, 2), (2, 2)]);
+        let texels = decode_bc1_block(&block[..8]);
+        assert_eq!(texels[0], [255, 0, 0, 255]);
+        assert_eq!(texels[1], [170, 0, 85, 255]);
+
+        // Swapped endpoints select the three-color mode, where index 3 is black.
+        let block = pack_bits(&[(0x001F, 16), (0xF800, 16), (3, 2), (2, 2)]);
+        let texels = decode_bc1_block(&block[..8]);
+        assert_eq!(texels[0], [0, 0, 0, 255]);
+        assert_eq!(texels[1], [127, 0, 127, 255]);
+    }
+
+    #[test]
+    fn bc4_unorm() {
+        let block = pack_bits(&[(255, 8), (0, 8), (0, 3), (1, 3), (2, 3)]);
+        let values = decode_bc4_block(&block[..8], false);
+        assert_eq!(values[..3], [255, 0, 218]);
+
+        // Six-value mode, with explicit 0 and 255.
+        let block = pack_bits(&[(10, 8), (60, 8), (2, 3), (6, 3), (7, 3)]);
+        let values = decode_bc4_block(&block[..8], false);
+        assert_eq!(values[..3], [20, 0, 255]);
+    }
+
+    #[test]
+    fn bc5_snorm() {
+        // -128 decodes as -127.
+        let red = [(0x7F, 8), (0x80, 8), (0, 3), (1, 3), (2, 3)];
+        let green = [(0x81, 8), (0x7F, 8), (0, 3), (1, 3), (7, 3)];
+        let mut block = [0u8; 16];
+        block[..8].copy_from_slice(&pack_bits(&red)[..8]);
+        block[8..].copy_from_slice(&pack_bits(&green)[..8]);
+
+        let texels = decode_bc5_snorm_block(&block);
+        assert_eq!(texels[0], [127, -127i8 as u8, 0, 127]);
+        assert_eq!(texels[1], [-127i8 as u8, 127, 0, 127]);
+        assert_eq!(texels[2], [90, 127, 0, 127]);
+    }
+
+    #[test]
+    fn bc7_mode6() {
+        // Mode 6: one subset, 7-bit RGBA endpoints, a P-bit per endpoint, 4-bit indices.
+        let mut fields = vec![(1 << 6, 7)];
+        for (e0, e1) in [(0, 127), (0, 0), (127, 0), (127, 127)] {
+            fields.push((e0, 7));
+            fields.push((e1, 7));
+        }
+        fields.push((1, 1));
+        fields.push((1, 1));
+        // Texel 0 is the anchor, with an implied zero top bit.
+        fields.push((0, 3));
+        fields.push((15, 4));
+        fields.push((8, 4));
+        let texels = decode_bc7_block(&pack_bits(&fields));
+
+        assert_eq!(texels[0], [1, 1, 255, 255]);
+        assert_eq!(texels[1], [255, 1, 1, 255]);
+        assert_eq!(texels[2], [136, 1, 120, 255]);
+    }
+
+    #[test]
+    fn bc7_mode1_partitions() {
+        // Mode 1: two subsets, 6-bit RGB endpoints, a shared P-bit per subset.
+        // Partition 0 puts the two left columns in subset 0.
+        let mut fields = vec![(1 << 1, 2), (0, 6)];
+        fields.extend([(63, 6), (63, 6), (0, 6), (0, 6)]);
+        fields.extend([(0, 6); 4]);
+        fields.extend([(0, 6), (0, 6), (63, 6), (63, 6)]);
+        fields.extend([(0, 1), (0, 1)]);
+        let texels = decode_bc7_block(&pack_bits(&fields));
+
+        assert_eq!(texels[0], [253, 0, 0, 255]);
+        assert_eq!(texels[2], [0, 0, 253, 255]);
+        assert_eq!(texels[4], [253, 0, 0, 255]);
+        assert_eq!(texels[15], [0, 0, 253, 255]);
+    }
+
+    #[test]
+    fn bc7_mode5_rotation() {
+        // Mode 5: one subset, 7-bit RGB and 8-bit alpha with separate 2-bit indices.
+        // Rotation 1 swaps red and alpha.
+        let mut fields = vec![(1 << 5, 6), (1, 2)];
+        fields.extend([(0, 7), (127, 7), (0, 7), (0, 7), (0, 7), (0, 7)]);
+        fields.extend([(255, 8), (0, 8)]);
+        fields.push((0, 1));
+        fields.extend([(3, 2); 15]);
+        fields.push((0, 1));
+        let texels = decode_bc7_block(&pack_bits(&fields));
+
+        assert_eq!(texels[0], [255, 0, 0, 0]);
+        assert_eq!(texels[1], [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc7_reserved_mode() {
+        assert_eq!(decode_bc7_block(&[0u8; 16]), [[0u8; 4]; 16]);
+    }
+
+    #[test]
+    fn bc7_anchors_in_their_subsets() {
+        for partition in 0..64 {
+            assert_eq!(bc7_subset(2, partition, 0), 0);
+            assert_eq!(
+                bc7_subset(2, partition, BC7_ANCHORS_2[partition] as usize),
+                1
+            );
+
+            assert_eq!(bc7_subset(3, partition, 0), 0);
+            let second = BC7_ANCHORS_3_SECOND[partition] as usize;
+            let third = BC7_ANCHORS_3_THIRD[partition] as usize;
+            assert_eq!(bc7_subset(3, partition, second), 1);
+            assert_eq!(bc7_subset(3, partition, third), 2);
+        }
+    }
+}
@@ -315,11 +315,14 @@ impl CreateGpuImage {
         assert_eq!(byte_offset, dds_data.len());
 
         let format = match dds.get_dxgi_format() {
+            Some(ddsfile::DxgiFormat::BC1_UNorm) => vk::Format::BC1_RGB_UNORM_BLOCK,
             Some(ddsfile::DxgiFormat::BC1_UNorm_sRGB) => vk::Format::BC1_RGB_SRGB_BLOCK,
             Some(ddsfile::DxgiFormat::BC3_UNorm) => vk::Format::BC3_UNORM_BLOCK,
             Some(ddsfile::DxgiFormat::BC3_UNorm_sRGB) => vk::Format::BC3_SRGB_BLOCK,
             Some(ddsfile::DxgiFormat::BC5_UNorm) => vk::Format::BC5_UNORM_BLOCK,
             Some(ddsfile::DxgiFormat::BC5_SNorm) => vk::Format::BC5_SNORM_BLOCK,
+            Some(ddsfile::DxgiFormat::BC7_UNorm) => vk::Format::BC7_UNORM_BLOCK,
+            Some(ddsfile::DxgiFormat::BC7_UNorm_sRGB) => vk::Format::BC7_SRGB_BLOCK,
             _ => todo!(
                 "DDS format dxgi:{:?} d3d:{:?} not supported yet",
                 dds.get_dxgi_format(),
@@ -1,3 +1,4 @@
+pub mod bc_decode;
 pub mod image;
 pub mod mesh;
 
@@ -5,6 +5,7 @@ use byteorder::{ByteOrder, NativeEndian, WriteBytesExt};
 use glam::{Mat4, Quat, Vec3, Vec4};
 use gltf::texture::TextureTransform;
 use kajiya_backend::bytes::into_byte_vec;
+use rust_shaders_shared::coordinate_system::CoordinateSystem;
 /*use render_core::{
     constants::MAX_VERTEX_STREAMS,
     device::RenderDevice,
@@ -258,6 +259,10 @@ pub struct LoadGltfScene {
     pub path: PathBuf,
     pub scale: f32,
     pub rotation: Quat,
+
+    /// Convention the scene is authored in. glTF mandates kajiya's right-handed, Y-up one,
+    /// the default, but some exporters write Z-up or left-handed data regardless.
+    pub coordinate_system: CoordinateSystem,
 }
 
 impl Hash for LoadGltfScene {
@@ -268,6 +273,7 @@ impl Hash for LoadGltfScene {
         self.rotation.y.to_ne_bytes().hash(state);
         self.rotation.z.to_ne_bytes().hash(state);
         self.rotation.w.to_ne_bytes().hash(state);
+        self.coordinate_system.hash(state);
     }
 }
 
@@ -305,18 +311,15 @@ impl LazyWorker for LoadGltfScene {
                         }
 
                         // Collect positions (required)
-                        let positions = if let Some(iter) = reader.read_positions() {
+                        let mut positions = if let Some(iter) = reader.read_positions() {
                             iter.collect::<Vec<_>>()
                         } else {
                             return;
                         };
 
-                        // Collect normals (required)
-                        let normals = if let Some(iter) = reader.read_normals() {
-                            iter.collect::<Vec<_>>()
-                        } else {
-                            return;
-                        };
+                        // Collect normals (optional; flat normals are calculated if missing)
+                        let mut normals =
+                            reader.read_normals().map(|iter| iter.collect::<Vec<_>>());
 
                         // Collect tangents (optional)
                         let (mut tangents, tangents_found) =
@@ -366,6 +369,31 @@ impl LazyWorker for LoadGltfScene {
                                 }
                             }
 
+                            if normals.is_none() {
+                                log::trace!("Mesh had no normals. Calculating flat normals...");
+
+                                // Flat normals can't be shared between faces, so un-weld the vertices.
+                                positions =
+                                    indices.iter().map(|&i| positions[i as usize]).collect();
+                                tangents = indices.iter().map(|&i| tangents[i as usize]).collect();
+                                uvs = indices.iter().map(|&i| uvs[i as usize]).collect();
+                                colors = indices.iter().map(|&i| colors[i as usize]).collect();
+                                material_ids = vec![res_material_index; positions.len()];
+                                indices = (0..positions.len() as u32).collect();
+
+                                normals = Some(
+                                    positions
+                                        .chunks_exact(3)
+                                        .flat_map(|tri| {
+                                            let [a, b, c] =
+                                                [tri[0], tri[1], tri[2]].map(Vec3::from);
+                                            let n = (b - a).cross(c - a).normalize_or_zero();
+                                            std::iter::repeat(n.into()).take(3)
+                                        })
+                                        .collect(),
+                                );
+                            }
+
                             if flip_winding_order {
                                 for tri in indices.chunks_exact_mut(3) {
                                     tri.swap(0, 2);
@@ -373,6 +401,8 @@ impl LazyWorker for LoadGltfScene {
                             }
                         }
 
+                        let normals = normals.unwrap();
+
                         if !tangents_found && uvs_found {
                             log::trace!(
                                 "Mesh had UVs but no tangents. Calculating the tangents..."
@@ -426,11 +456,13 @@ impl LazyWorker for LoadGltfScene {
                 }
             };
 
-            let xform = Mat4::from_scale_rotation_translation(
-                Vec3::splat(self.scale),
-                self.rotation,
-                Vec3::ZERO,
-            );
+            // Mirroring conversions flip the winding order, which `process_node` accounts for.
+            let xform = Mat4::from_mat3(self.coordinate_system.to_kajiya())
+                * Mat4::from_scale_rotation_translation(
+                    Vec3::splat(self.scale),
+                    self.rotation,
+                    Vec3::ZERO,
+                );
             for node in scene.nodes() {
                 iter_gltf_node_tree(&node, xform, &mut process_node);
             }
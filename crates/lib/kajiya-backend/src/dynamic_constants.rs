@@ -5,6 +5,7 @@ use log::{debug, error, info, trace, warn};
 use std::mem::{align_of, size_of};
 use vulkan::buffer::Buffer;
 
+/// Default size of the per-frame dynamic constants region.
 pub const DYNAMIC_CONSTANTS_SIZE_BYTES: usize = 1024 * 1024 * 16;
 pub const DYNAMIC_CONSTANTS_BUFFER_COUNT: usize = 2;
 
@@ -22,26 +23,40 @@ pub const MAX_DYNAMIC_CONSTANTS_STORAGE_BUFFER_BYTES: usize = 1024 * 1024;
 
 pub struct DynamicConstants {
     pub buffer: Buffer,
+    frame_size_bytes: usize,
     frame_offset_bytes: usize,
     frame_parity: usize,
 }
 
 impl DynamicConstants {
-    pub fn new(buffer: Buffer) -> Self {
+    /// `buffer` must hold `DYNAMIC_CONSTANTS_BUFFER_COUNT` regions of `frame_size_bytes` each.
+    pub fn new(buffer: Buffer, frame_size_bytes: usize) -> Self {
+        assert!(buffer.desc.size >= frame_size_bytes * DYNAMIC_CONSTANTS_BUFFER_COUNT);
+
         Self {
             buffer,
+            frame_size_bytes,
             frame_offset_bytes: 0,
             frame_parity: 0,
         }
     }
 
+    pub fn frame_size_bytes(&self) -> usize {
+        self.frame_size_bytes
+    }
+
     pub fn advance_frame(&mut self) {
         self.frame_parity = (self.frame_parity + 1) % DYNAMIC_CONSTANTS_BUFFER_COUNT;
         self.frame_offset_bytes = 0;
     }
 
+    /// Bytes pushed since the last `advance_frame`.
+    pub fn frame_bytes_used(&self) -> usize {
+        self.frame_offset_bytes
+    }
+
     pub fn current_offset(&self) -> u32 {
-        (self.frame_parity * DYNAMIC_CONSTANTS_SIZE_BYTES + self.frame_offset_bytes) as u32
+        (self.frame_parity * self.frame_size_bytes + self.frame_offset_bytes) as u32
     }
 
     pub fn current_device_address(&self, device: &crate::Device) -> vk::DeviceAddress {
@@ -50,7 +65,7 @@ impl DynamicConstants {
 
     pub fn push<T: Copy>(&mut self, t: &T) -> u32 {
         let t_size = size_of::<T>();
-        assert!(self.frame_offset_bytes + t_size < DYNAMIC_CONSTANTS_SIZE_BYTES);
+        assert!(self.frame_offset_bytes + t_size < self.frame_size_bytes);
 
         let buffer_offset = self.current_offset() as usize;
         let dst = &mut self.buffer.allocation.mapped_slice_mut().unwrap()
@@ -69,7 +84,7 @@ impl DynamicConstants {
         let t_size = size_of::<T>();
         let t_align = align_of::<T>();
 
-        assert!(self.frame_offset_bytes + t_size < DYNAMIC_CONSTANTS_SIZE_BYTES);
+        assert!(self.frame_offset_bytes + t_size < self.frame_size_bytes);
         assert!(DYNAMIC_CONSTANTS_ALIGNMENT % t_align == 0);
 
         let buffer_offset = self.current_offset() as usize;
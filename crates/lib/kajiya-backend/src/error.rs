@@ -13,6 +13,13 @@ pub enum BackendError {
 
     #[error("Invalid resource access: {info:?}")]
     ResourceAccess { info: String },
+
+    #[error("Allocating {size} bytes for {name:?} would exceed the memory budget; {available} bytes available")]
+    OverBudget {
+        name: String,
+        size: u64,
+        available: u64,
+    },
 }
 
 impl From<ash::vk::Result> for BackendError {
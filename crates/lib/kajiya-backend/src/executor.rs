@@ -0,0 +1,29 @@
+//! Configuration of the global `smol` executor, which evaluates `LazyCache` work
+//! such as shader compilation and asset loading.
+
+const THREADS_ENV_VAR: &str = "SMOL_THREADS";
+
+/// Sets the number of worker threads of the global executor. The thread pool is created
+/// when it's first used, so this must be called before anything gets spawned on it;
+/// later calls have no effect.
+pub fn set_worker_thread_count(count: usize) {
+    std::env::set_var(THREADS_ENV_VAR, count.max(1).to_string());
+}
+
+/// Deliberately far more threads than cores: shader include loading blocks on nested
+/// `LazyCache` evaluations from within worker tasks, so a small pool can deadlock
+/// while compiling many shaders at once.
+// TODO: scale with the core count once include loading is async
+pub fn default_worker_thread_count() -> usize {
+    64
+}
+
+/// Uses `count` worker threads if given. Otherwise keeps the count from the `SMOL_THREADS`
+/// environment variable if it's set, or uses `default_worker_thread_count`.
+pub fn init_worker_threads(count: Option<usize>) {
+    match count {
+        Some(count) => set_worker_thread_count(count),
+        None if std::env::var_os(THREADS_ENV_VAR).is_some() => {}
+        None => set_worker_thread_count(default_worker_thread_count()),
+    }
+}
@@ -2,6 +2,7 @@ pub mod bytes;
 pub mod chunky_list;
 pub mod dynamic_constants;
 mod error;
+pub mod executor;
 pub mod file;
 pub mod pipeline_cache;
 pub mod rust_shader_compiler;
@@ -1,14 +1,24 @@
 use crate::{
     rust_shader_compiler::CompileRustShader,
-    shader_compiler::{CompileShader, CompiledShader},
+    shader_compiler::{
+        CachedShaderBinary, CompileShader, CompiledShader, ShaderBinaryKey, SHADER_BINARY_CACHE,
+    },
     vulkan::{
         ray_tracing::{create_ray_tracing_pipeline, RayTracingPipeline, RayTracingPipelineDesc},
         shader::*,
     },
 };
+use bytes::Bytes;
+use futures::{future::Either, Future, StreamExt};
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
-use std::{collections::HashMap, sync::Arc};
+use nanoserde::{DeBin, SerBin};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use turbosloth::*;
 
 #[derive(Clone, Copy, Hash, Eq, PartialEq)]
@@ -16,8 +26,11 @@ pub struct ComputePipelineHandle(usize);
 
 struct ComputePipelineCacheEntry {
     lazy_handle: Lazy<CompiledShader>,
+    name: String,
     desc: ComputePipelineDesc,
     pipeline: Option<Arc<ComputePipeline>>,
+    rebuild_pending: bool,
+    error: Option<String>,
 }
 
 #[derive(Clone, Copy, Hash, Eq, PartialEq)]
@@ -26,6 +39,39 @@ pub struct RasterPipelineHandle(usize);
 #[derive(Clone, Copy, Hash, Eq, PartialEq)]
 pub struct RtPipelineHandle(usize);
 
+/// Any of the pipeline handles, for queries common to all kinds of pipelines.
+#[derive(Clone, Copy, Hash, Eq, PartialEq)]
+pub enum PipelineHandle {
+    Compute(ComputePipelineHandle),
+    Raster(RasterPipelineHandle),
+    RayTracing(RtPipelineHandle),
+}
+
+impl From<ComputePipelineHandle> for PipelineHandle {
+    fn from(handle: ComputePipelineHandle) -> Self {
+        Self::Compute(handle)
+    }
+}
+
+impl From<RasterPipelineHandle> for PipelineHandle {
+    fn from(handle: RasterPipelineHandle) -> Self {
+        Self::Raster(handle)
+    }
+}
+
+impl From<RtPipelineHandle> for PipelineHandle {
+    fn from(handle: RtPipelineHandle) -> Self {
+        Self::RayTracing(handle)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PipelineStatus {
+    Compiling,
+    Ready,
+    Failed(String),
+}
+
 pub struct CompiledPipelineShaders {
     shaders: Vec<PipelineShader<Arc<CompiledShader>>>,
 }
@@ -78,14 +124,20 @@ impl LazyWorker for CompilePipelineShaders {
 
 struct RasterPipelineCacheEntry {
     lazy_handle: Lazy<CompiledPipelineShaders>,
+    name: String,
     desc: RasterPipelineDesc,
     pipeline: Option<Arc<RasterPipeline>>,
+    rebuild_pending: bool,
+    error: Option<String>,
 }
 
 struct RtPipelineCacheEntry {
     lazy_handle: Lazy<CompiledPipelineShaders>,
+    name: String,
     desc: RayTracingPipelineDesc,
     pipeline: Option<Arc<RayTracingPipeline>>,
+    rebuild_pending: bool,
+    error: Option<String>,
 }
 
 pub struct PipelineCache {
@@ -98,6 +150,9 @@ pub struct PipelineCache {
     compute_shader_to_handle: HashMap<ShaderSource, ComputePipelineHandle>,
     raster_shaders_to_handle: HashMap<Vec<PipelineShaderDesc>, RasterPipelineHandle>,
     rt_shaders_to_handle: HashMap<Vec<PipelineShaderDesc>, RtPipelineHandle>,
+
+    slow_build_warning_threshold: Duration,
+    shader_dump_dir: Option<Arc<PathBuf>>,
 }
 
 impl PipelineCache {
@@ -113,6 +168,9 @@ impl PipelineCache {
 
             raster_shaders_to_handle: Default::default(),
             rt_shaders_to_handle: Default::default(),
+
+            slow_build_warning_threshold: Duration::from_secs(5),
+            shader_dump_dir: None,
         }
     }
 
@@ -138,8 +196,11 @@ impl PipelineCache {
                     handle,
                     ComputePipelineCacheEntry {
                         lazy_handle: compile_task,
+                        name: format!("{:?}", desc.source),
                         desc: desc.clone(),
                         pipeline: None,
+                        rebuild_pending: false,
+                        error: None,
                     },
                 );
                 vacant.insert(handle);
@@ -176,8 +237,11 @@ impl PipelineCache {
                     shader_descs: shaders.to_vec(),
                 }
                 .into_lazy(),
+                name: pipeline_shaders_name(shaders),
                 desc: desc.clone(),
                 pipeline: None,
+                rebuild_pending: false,
+                error: None,
             },
         );
         handle
@@ -210,8 +274,11 @@ impl PipelineCache {
                     shader_descs: shaders.to_vec(),
                 }
                 .into_lazy(),
+                name: pipeline_shaders_name(shaders),
                 desc: desc.clone(),
                 pipeline: None,
+                rebuild_pending: false,
+                error: None,
             },
         );
         handle
@@ -226,25 +293,37 @@ impl PipelineCache {
             .unwrap()
     }
 
+    /// Human-readable identification of the pipeline's shaders, e.g. for crash markers.
+    pub fn compute_name(&self, handle: ComputePipelineHandle) -> &str {
+        &self.compute_entries[&handle].name
+    }
+
+    pub fn raster_name(&self, handle: RasterPipelineHandle) -> &str {
+        &self.raster_entries[&handle].name
+    }
+
+    pub fn ray_tracing_name(&self, handle: RtPipelineHandle) -> &str {
+        &self.rt_entries[&handle].name
+    }
+
+    // Pipelines whose shaders changed on disk are rebuilt, but stay usable until
+    // the new version builds successfully.
     fn invalidate_stale_pipelines(&mut self) {
         for entry in self.compute_entries.values_mut() {
             if entry.pipeline.is_some() && entry.lazy_handle.is_stale() {
-                // TODO: release
-                entry.pipeline = None;
+                entry.rebuild_pending = true;
             }
         }
 
         for entry in self.raster_entries.values_mut() {
             if entry.pipeline.is_some() && entry.lazy_handle.is_stale() {
-                // TODO: release
-                entry.pipeline = None;
+                entry.rebuild_pending = true;
             }
         }
 
         for entry in self.rt_entries.values_mut() {
             if entry.pipeline.is_some() && entry.lazy_handle.is_stale() {
-                // TODO: release
-                entry.pipeline = None;
+                entry.rebuild_pending = true;
             }
         }
     }
@@ -253,131 +332,441 @@ impl PipelineCache {
         &mut self,
         device: &Arc<crate::vulkan::device::Device>,
     ) -> anyhow::Result<()> {
+        self.compile_pending(device, &mut |_| {})
+    }
+
+    /// Blocks until every registered pipeline is compiled, e.g. during a loading screen.
+    /// Unlike `prepare_frame`, this is never done implicitly; it's up to the app to call it.
+    ///
+    /// `on_progress` is invoked once after each pipeline is created.
+    pub fn compile_all(
+        &mut self,
+        device: &Arc<crate::vulkan::device::Device>,
+        mut on_progress: impl FnMut(PipelineCompileProgress),
+    ) -> anyhow::Result<()> {
+        self.invalidate_stale_pipelines();
+        self.compile_pending(device, &mut on_progress)
+    }
+
+    fn compile_pending(
+        &mut self,
+        device: &Arc<crate::vulkan::device::Device>,
+        on_progress: &mut dyn FnMut(PipelineCompileProgress),
+    ) -> anyhow::Result<()> {
+        let slow_build_threshold = self.slow_build_warning_threshold;
+        let shader_dump_dir = &self.shader_dump_dir;
+
         // Prepare build tasks for compute
         let compute = self.compute_entries.iter().filter_map(|(&handle, entry)| {
-            entry.pipeline.is_none().then(|| {
+            (entry.pipeline.is_none() || entry.rebuild_pending).then(|| {
                 let task = entry.lazy_handle.eval(&self.lazy_cache);
-                smol::spawn(async move {
-                    task.await
-                        .map(|compiled| CompileTaskOutput::Compute { handle, compiled })
-                })
+                let device = device.clone();
+                let desc = entry.desc.clone();
+                let shader_dump_dir = shader_dump_dir.clone();
+
+                smol::spawn(warn_if_slow(
+                    entry.name.clone(),
+                    slow_build_threshold,
+                    async move {
+                        let pipeline = async move {
+                            let compiled = task.await?;
+                            let pipeline = smol::unblock(move || {
+                                log::trace!(
+                                    "Creating compute pipeline {:?}:{:?}",
+                                    compiled.name,
+                                    desc.source.entry(),
+                                );
+
+                                if let Some(dir) = &shader_dump_dir {
+                                    dump_shader(dir, &compiled.name, &compiled.spirv);
+                                }
+
+                                create_compute_pipeline(device.as_ref(), &compiled.spirv, &desc)
+                            })
+                            .await;
+
+                            Ok::<_, anyhow::Error>(Arc::new(pipeline))
+                        }
+                        .await;
+
+                        BuiltPipeline::Compute { handle, pipeline }
+                    },
+                ))
             })
         });
 
         // Prepare build tasks for raster
         let raster = self.raster_entries.iter().filter_map(|(&handle, entry)| {
-            entry.pipeline.is_none().then(|| {
+            (entry.pipeline.is_none() || entry.rebuild_pending).then(|| {
                 let task = entry.lazy_handle.eval(&self.lazy_cache);
-                smol::spawn(async move {
-                    task.await
-                        .map(|compiled| CompileTaskOutput::Raster { handle, compiled })
-                })
+                let device = device.clone();
+                let desc = entry.desc.clone();
+                let shader_dump_dir = shader_dump_dir.clone();
+
+                smol::spawn(warn_if_slow(
+                    entry.name.clone(),
+                    slow_build_threshold,
+                    async move {
+                        let pipeline = async move {
+                            let compiled = task.await?;
+                            let pipeline = smol::unblock(move || {
+                                log::trace!(
+                                    "Creating raster pipeline {}",
+                                    compiled
+                                        .shaders
+                                        .iter()
+                                        .map(|shader| format!(
+                                            "{:?}:{:?}",
+                                            shader.desc.stage, shader.desc.entry
+                                        ))
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                );
+
+                                if let Some(dir) = &shader_dump_dir {
+                                    dump_pipeline_shaders(dir, &compiled);
+                                }
+
+                                create_raster_pipeline(
+                                    device.as_ref(),
+                                    &pipeline_shader_code(&compiled),
+                                    &desc,
+                                )
+                            })
+                            .await?;
+
+                            Ok::<_, anyhow::Error>(Arc::new(pipeline))
+                        }
+                        .await;
+
+                        BuiltPipeline::Raster { handle, pipeline }
+                    },
+                ))
             })
         });
 
         // Prepare build tasks for rt
         let rt = self.rt_entries.iter().filter_map(|(&handle, entry)| {
-            entry.pipeline.is_none().then(|| {
+            (entry.pipeline.is_none() || entry.rebuild_pending).then(|| {
                 let task = entry.lazy_handle.eval(&self.lazy_cache);
-                smol::spawn(async move {
-                    task.await
-                        .map(|compiled| CompileTaskOutput::Rt { handle, compiled })
-                })
+                let device = device.clone();
+                let desc = entry.desc.clone();
+                let shader_dump_dir = shader_dump_dir.clone();
+
+                smol::spawn(warn_if_slow(
+                    entry.name.clone(),
+                    slow_build_threshold,
+                    async move {
+                        let pipeline = async move {
+                            let compiled = task.await?;
+                            let pipeline = smol::unblock(move || {
+                                log::trace!(
+                                    "Creating rt pipeline {}",
+                                    compiled
+                                        .shaders
+                                        .iter()
+                                        .map(|shader| format!(
+                                            "{} {:?}:{:?}",
+                                            shader.code.name, shader.desc.stage, shader.desc.entry
+                                        ))
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                );
+
+                                if let Some(dir) = &shader_dump_dir {
+                                    dump_pipeline_shaders(dir, &compiled);
+                                }
+
+                                create_ray_tracing_pipeline(
+                                    device.as_ref(),
+                                    &pipeline_shader_code(&compiled),
+                                    &desc,
+                                )
+                            })
+                            .await?;
+
+                            Ok::<_, anyhow::Error>(Arc::new(pipeline))
+                        }
+                        .await;
+
+                        BuiltPipeline::Rt { handle, pipeline }
+                    },
+                ))
             })
         });
 
         // Gather all the build tasks together
-        let shader_tasks: Vec<_> = compute.chain(raster).chain(rt).collect();
-
-        if !shader_tasks.is_empty() {
-            // Compile all the things
-            let compiled: Vec<CompileTaskOutput> =
-                smol::block_on(futures::future::try_join_all(shader_tasks))?;
-
-            // Build pipelines from all compiled shaders
-            for compiled in compiled {
-                match compiled {
-                    CompileTaskOutput::Compute { handle, compiled } => {
-                        let entry = self.compute_entries.get_mut(&handle).unwrap();
-                        log::trace!(
-                            "Creating compute pipeline {:?}:{:?}",
-                            compiled.name,
-                            entry.desc.source.entry(),
-                        );
-                        entry.pipeline = Some(Arc::new(create_compute_pipeline(
-                            device.as_ref(),
-                            &compiled.spirv,
-                            &entry.desc,
-                        )));
-                    }
-                    CompileTaskOutput::Raster { handle, compiled } => {
-                        let entry = self.raster_entries.get_mut(&handle).unwrap();
-                        log::trace!(
-                            "Creating raster pipeline {}",
-                            compiled
-                                .shaders
-                                .iter()
-                                .map(|shader| format!(
-                                    "{:?}:{:?}",
-                                    shader.desc.stage, shader.desc.entry
-                                ))
-                                .collect::<Vec<_>>()
-                                .join(", ")
-                        );
+        let mut build_tasks: futures::stream::FuturesUnordered<_> =
+            compute.chain(raster).chain(rt).collect();
+
+        let total = build_tasks.len();
+        let mut completed = 0;
+
+        // Shaders are compiled, and pipelines created on the worker pool; the calling thread
+        // only waits for them, and stores the results.
+        // Failures are recorded per pipeline, but don't stop the others from building;
+        // the first one is reported once all are done.
+        let mut first_error = None;
+        while let Some(built) = smol::block_on(build_tasks.next()) {
+            if let Err(err) = self.store_pipeline(built) {
+                first_error.get_or_insert(err);
+            }
 
-                        let compiled_shaders = compiled
-                            .shaders
-                            .iter()
-                            .map(|shader| PipelineShader {
-                                code: shader.code.spirv.clone(),
-                                desc: shader.desc.clone(),
-                            })
-                            .collect::<Vec<_>>();
+            completed += 1;
+            on_progress(PipelineCompileProgress { completed, total });
+        }
 
-                        // TODO: defer and handle the error
-                        entry.pipeline = Some(Arc::new(
-                            create_raster_pipeline(device.as_ref(), &compiled_shaders, &entry.desc)
-                                .expect("create_raster_pipeline"),
-                        ));
-                    }
-                    CompileTaskOutput::Rt { handle, compiled } => {
-                        let entry = self.rt_entries.get_mut(&handle).unwrap();
-                        log::trace!(
-                            "Creating rt pipeline {}",
-                            compiled
-                                .shaders
-                                .iter()
-                                .map(|shader| format!(
-                                    "{} {:?}:{:?}",
-                                    shader.code.name, shader.desc.stage, shader.desc.entry
-                                ))
-                                .collect::<Vec<_>>()
-                                .join(", ")
-                        );
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
 
-                        let compiled_shaders = compiled
-                            .shaders
-                            .iter()
-                            .map(|shader| PipelineShader {
-                                code: shader.code.spirv.clone(),
-                                desc: shader.desc.clone(),
-                            })
-                            .collect::<Vec<_>>();
-
-                        // TODO: defer and handle the error
-                        entry.pipeline = Some(Arc::new(
-                            create_ray_tracing_pipeline(
-                                device.as_ref(),
-                                &compiled_shaders,
-                                &entry.desc,
-                            )
-                            .expect("create_ray_tracing_pipeline"),
-                        ));
+    fn store_pipeline(&mut self, built: BuiltPipeline) -> anyhow::Result<()> {
+        fn store<T>(
+            name: &str,
+            pipeline: &mut Option<Arc<T>>,
+            rebuild_pending: &mut bool,
+            error: &mut Option<String>,
+            built: anyhow::Result<Arc<T>>,
+        ) -> anyhow::Result<()> {
+            *rebuild_pending = false;
+
+            match built {
+                Ok(built) => {
+                    // TODO: release the previous pipeline
+                    *pipeline = Some(built);
+                    *error = None;
+                    Ok(())
+                }
+                Err(err) => {
+                    *error = Some(format!("{:#}", err));
+
+                    // A hot-reloaded shader failed to build. Keep rendering with the last
+                    // working version, and try again once the shader changes.
+                    if pipeline.is_some() {
+                        error!(
+                            "Failed to rebuild pipeline {}; keeping the previous version: {:#}",
+                            name, err
+                        );
+                        Ok(())
+                    } else {
+                        Err(err)
                     }
                 }
             }
         }
 
+        match built {
+            BuiltPipeline::Compute { handle, pipeline } => {
+                let entry = self.compute_entries.get_mut(&handle).unwrap();
+                store(
+                    &entry.name,
+                    &mut entry.pipeline,
+                    &mut entry.rebuild_pending,
+                    &mut entry.error,
+                    pipeline,
+                )
+            }
+            BuiltPipeline::Raster { handle, pipeline } => {
+                let entry = self.raster_entries.get_mut(&handle).unwrap();
+                store(
+                    &entry.name,
+                    &mut entry.pipeline,
+                    &mut entry.rebuild_pending,
+                    &mut entry.error,
+                    pipeline,
+                )
+            }
+            BuiltPipeline::Rt { handle, pipeline } => {
+                let entry = self.rt_entries.get_mut(&handle).unwrap();
+                store(
+                    &entry.name,
+                    &mut entry.pipeline,
+                    &mut entry.rebuild_pending,
+                    &mut entry.error,
+                    pipeline,
+                )
+            }
+        }
+    }
+
+    /// Whether the pipeline can be used, is yet to be built, or failed to build.
+    ///
+    /// Failed pipelines are retried on every `prepare_frame`, e.g. to pick up shader
+    /// fixes when hot-reloading; they're reported as `Failed` until a build succeeds.
+    /// If a previously working pipeline fails to rebuild after its shaders changed,
+    /// it's reported as `Failed` too, but the previous version keeps being used,
+    /// and the build is only retried once the shaders change again.
+    pub fn status(&self, handle: impl Into<PipelineHandle>) -> PipelineStatus {
+        let (built, error) = match handle.into() {
+            PipelineHandle::Compute(handle) => {
+                let entry = &self.compute_entries[&handle];
+                (entry.pipeline.is_some(), &entry.error)
+            }
+            PipelineHandle::Raster(handle) => {
+                let entry = &self.raster_entries[&handle];
+                (entry.pipeline.is_some(), &entry.error)
+            }
+            PipelineHandle::RayTracing(handle) => {
+                let entry = &self.rt_entries[&handle];
+                (entry.pipeline.is_some(), &entry.error)
+            }
+        };
+
+        match (error, built) {
+            (Some(error), _) => PipelineStatus::Failed(error.clone()),
+            (None, true) => PipelineStatus::Ready,
+            (None, false) => PipelineStatus::Compiling,
+        }
+    }
+
+    /// Forgets the last build error of the pipeline, and drops the pipeline itself,
+    /// so that it's built from scratch by the next `prepare_frame`.
+    pub fn clear_error(&mut self, handle: impl Into<PipelineHandle>) {
+        // TODO: release the dropped pipelines
+        match handle.into() {
+            PipelineHandle::Compute(handle) => {
+                let entry = self.compute_entries.get_mut(&handle).unwrap();
+                entry.pipeline = None;
+                entry.error = None;
+            }
+            PipelineHandle::Raster(handle) => {
+                let entry = self.raster_entries.get_mut(&handle).unwrap();
+                entry.pipeline = None;
+                entry.error = None;
+            }
+            PipelineHandle::RayTracing(handle) => {
+                let entry = self.rt_entries.get_mut(&handle).unwrap();
+                entry.pipeline = None;
+                entry.error = None;
+            }
+        }
+    }
+
+    /// Pipelines whose most recent build failed, along with their names and errors.
+    pub fn failed_pipelines(&self) -> impl Iterator<Item = (PipelineHandle, &str, &str)> {
+        let compute = self.compute_entries.iter().filter_map(|(&handle, entry)| {
+            let error = entry.error.as_deref()?;
+            Some((PipelineHandle::Compute(handle), entry.name.as_str(), error))
+        });
+        let raster = self.raster_entries.iter().filter_map(|(&handle, entry)| {
+            let error = entry.error.as_deref()?;
+            Some((PipelineHandle::Raster(handle), entry.name.as_str(), error))
+        });
+        let rt = self.rt_entries.iter().filter_map(|(&handle, entry)| {
+            let error = entry.error.as_deref()?;
+            Some((
+                PipelineHandle::RayTracing(handle),
+                entry.name.as_str(),
+                error,
+            ))
+        });
+
+        compute.chain(raster).chain(rt)
+    }
+
+    /// Pipelines taking longer than this to compile and create get a warning in the log,
+    /// naming their shaders. The build itself is never interrupted.
+    pub fn set_slow_build_warning_threshold(&mut self, threshold: Duration) {
+        self.slow_build_warning_threshold = threshold;
+    }
+
+    /// When set, every shader gets written to `dir` as its pipeline is built: the SPIR-V
+    /// binary (`.spv`), its disassembly (`.spvasm`), and the descriptor sets found by
+    /// reflection (`.reflection.txt`). Useful for diagnosing bindings which aren't written,
+    /// or set layouts which come out empty.
+    pub fn set_shader_dump_dir(&mut self, dir: Option<PathBuf>) {
+        self.shader_dump_dir = dir.map(Arc::new);
+    }
+
+    /// Loads shader binaries and driver pipeline data saved by `save_to_disk`, so that
+    /// pipelines built from then on can skip shader compilation, and parts of pipeline
+    /// creation. Call it before the first `prepare_frame`.
+    ///
+    /// Cached shader binaries are only used if the preprocessed source, including all
+    /// of its includes, still matches what they were compiled from. Driver data is dropped
+    /// if it comes from a different GPU or driver.
+    pub fn load_from_disk(
+        &mut self,
+        device: &Arc<crate::vulkan::device::Device>,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        let persisted = PersistedPipelineCache::deserialize_bin(&bytes).map_err(|err| {
+            anyhow::anyhow!("Failed to parse the pipeline cache {:?}: {:?}", path, err)
+        })?;
+
+        anyhow::ensure!(
+            persisted.version == PERSISTED_PIPELINE_CACHE_VERSION,
+            "The pipeline cache {:?} is of an unsupported version {}",
+            path,
+            persisted.version
+        );
+
+        let shader_count = persisted.shaders.len();
+        SHADER_BINARY_CACHE
+            .lock()
+            .extend(persisted.shaders.into_iter().map(|shader| {
+                (
+                    ShaderBinaryKey {
+                        path: shader.path,
+                        profile: shader.profile,
+                    },
+                    CachedShaderBinary {
+                        source_hash: shader.source_hash,
+                        spirv: shader.spirv.into(),
+                    },
+                )
+            }));
+
+        if persisted.pipeline_cache_uuid[..] == device.pdevice.properties.pipeline_cache_uuid[..] {
+            device.merge_pipeline_cache_data(&persisted.driver_data)?;
+        } else {
+            info!(
+                "Ignoring driver data in {:?}, as it was saved with a different GPU or driver",
+                path
+            );
+        }
+
+        info!("Loaded {} shader binaries from {:?}", shader_count, path);
+
+        Ok(())
+    }
+
+    /// Writes all shader binaries compiled so far, and the driver's pipeline data to `path`,
+    /// for `load_from_disk` to pick up in a later run.
+    pub fn save_to_disk(
+        &self,
+        device: &Arc<crate::vulkan::device::Device>,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        let shaders = SHADER_BINARY_CACHE
+            .lock()
+            .iter()
+            .map(|(key, binary)| PersistedShaderBinary {
+                path: key.path.clone(),
+                profile: key.profile.clone(),
+                source_hash: binary.source_hash,
+                spirv: binary.spirv.to_vec(),
+            })
+            .collect();
+
+        let persisted = PersistedPipelineCache {
+            version: PERSISTED_PIPELINE_CACHE_VERSION,
+            pipeline_cache_uuid: device.pdevice.properties.pipeline_cache_uuid.to_vec(),
+            driver_data: device.pipeline_cache_data()?,
+            shaders,
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, persisted.serialize_bin())?;
+
         Ok(())
     }
 
@@ -392,17 +781,135 @@ impl PipelineCache {
     }
 }
 
-enum CompileTaskOutput {
+fn pipeline_shaders_name(shaders: &[PipelineShaderDesc]) -> String {
+    shaders
+        .iter()
+        .map(|shader| format!("{:?}:{:?}", shader.source, shader.entry))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Bump when changing the layout of `PersistedPipelineCache`, how source hashes are computed,
+// or the version of dxc that hassle-rs loads. The dxc version can't be queried, so it isn't
+// part of the hashes; compiler arguments are.
+const PERSISTED_PIPELINE_CACHE_VERSION: u32 = 2;
+
+#[derive(SerBin, DeBin)]
+struct PersistedPipelineCache {
+    version: u32,
+    pipeline_cache_uuid: Vec<u8>,
+    driver_data: Vec<u8>,
+    shaders: Vec<PersistedShaderBinary>,
+}
+
+#[derive(SerBin, DeBin)]
+struct PersistedShaderBinary {
+    path: String,
+    profile: String,
+    source_hash: u64,
+    spirv: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineCompileProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+enum BuiltPipeline {
     Compute {
         handle: ComputePipelineHandle,
-        compiled: Arc<CompiledShader>,
+        pipeline: anyhow::Result<Arc<ComputePipeline>>,
     },
     Raster {
         handle: RasterPipelineHandle,
-        compiled: Arc<CompiledPipelineShaders>,
+        pipeline: anyhow::Result<Arc<RasterPipeline>>,
     },
     Rt {
         handle: RtPipelineHandle,
-        compiled: Arc<CompiledPipelineShaders>,
+        pipeline: anyhow::Result<Arc<RayTracingPipeline>>,
     },
 }
+
+fn pipeline_shader_code(compiled: &CompiledPipelineShaders) -> Vec<PipelineShader<Bytes>> {
+    compiled
+        .shaders
+        .iter()
+        .map(|shader| PipelineShader {
+            code: shader.code.spirv.clone(),
+            desc: shader.desc.clone(),
+        })
+        .collect()
+}
+
+fn dump_pipeline_shaders(dir: &Path, compiled: &CompiledPipelineShaders) {
+    for shader in &compiled.shaders {
+        dump_shader(
+            dir,
+            &format!("{}_{:?}", shader.code.name, shader.desc.stage),
+            &shader.code.spirv,
+        );
+    }
+}
+
+fn dump_shader(dir: &Path, name: &str, spirv: &[u8]) {
+    use rspirv::binary::Disassemble;
+
+    let file_stem: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    let dump = || -> anyhow::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(format!("{}.spv", file_stem)), spirv)?;
+
+        let module = rspirv::dr::load_bytes(spirv)
+            .map_err(|err| anyhow::anyhow!("Failed to parse SPIR-V: {:?}", err))?;
+        std::fs::write(
+            dir.join(format!("{}.spvasm", file_stem)),
+            module.disassemble(),
+        )?;
+
+        let descriptor_sets = rspirv_reflect::Reflection::new_from_spirv(spirv)
+            .and_then(|reflection| reflection.get_descriptor_sets())
+            .map_err(|err| anyhow::anyhow!("Failed to reflect: {:?}", err))?;
+        std::fs::write(
+            dir.join(format!("{}.reflection.txt", file_stem)),
+            format!("{}\n\n{:#?}\n", name, descriptor_sets),
+        )?;
+
+        Ok(())
+    };
+
+    if let Err(err) = dump() {
+        log::warn!("Could not dump shader {}: {:#}", name, err);
+    }
+}
+
+/// Runs `build`, logging a warning if it hasn't finished after `threshold`.
+async fn warn_if_slow<T>(name: String, threshold: Duration, build: impl Future<Output = T>) -> T {
+    let t0 = Instant::now();
+
+    let watchdog = async {
+        smol::Timer::after(threshold).await;
+        log::warn!(
+            "Pipeline {} has been building for over {:.1}s",
+            name,
+            threshold.as_secs_f32()
+        );
+    };
+
+    let res = match futures::future::select(Box::pin(build), Box::pin(watchdog)).await {
+        Either::Left((res, _)) => return res,
+        Either::Right(((), build)) => build.await,
+    };
+
+    log::warn!(
+        "Pipeline {} took {:.1}s to build",
+        name,
+        t0.elapsed().as_secs_f32()
+    );
+
+    res
+}
@@ -1,10 +1,33 @@
 use crate::file::LoadFile;
 use anyhow::{anyhow, bail, Context, Result};
 use bytes::Bytes;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
 use relative_path::RelativePathBuf;
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashMap, hash::Hasher, path::PathBuf, sync::Arc};
 use turbosloth::*;
 
+lazy_static! {
+    /// SPIR-V of HLSL shaders compiled so far, keyed by path and profile, along with
+    /// a hash of the preprocessed source they were compiled from. Persisted along with
+    /// the pipeline cache, so that unchanged shaders don't need to be compiled again
+    /// after a restart.
+    pub(crate) static ref SHADER_BINARY_CACHE: Mutex<HashMap<ShaderBinaryKey, CachedShaderBinary>> =
+        Default::default();
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub(crate) struct ShaderBinaryKey {
+    pub path: String,
+    pub profile: String,
+}
+
+#[derive(Clone)]
+pub(crate) struct CachedShaderBinary {
+    pub source_hash: u64,
+    pub spirv: Bytes,
+}
+
 pub struct CompiledShader {
     pub name: String,
     pub spirv: Bytes,
@@ -50,7 +73,43 @@ impl LazyWorker for CompileShader {
                     .map_err(|err| anyhow!("{}", err))
                     .with_context(|| format!("shader path: {:?}", self.path))?;
                 let target_profile = format!("{}_6_4", self.profile);
-                let spirv = compile_generic_shader_hlsl_impl(&name, &source, &target_profile)?;
+                let key = ShaderBinaryKey {
+                    path: file_path,
+                    profile: target_profile,
+                };
+
+                // Includes are already expanded, so any change to them is caught too.
+                // Persisted across runs, so this needs a hasher that's stable across builds.
+                let source_hash = {
+                    let mut hasher = fxhash::FxHasher64::default();
+                    for arg in DXC_ARGS {
+                        hasher.write(arg.as_bytes());
+                        hasher.write_u8(0);
+                    }
+                    for chunk in &source {
+                        hasher.write(chunk.source.as_bytes());
+                    }
+                    hasher.finish()
+                };
+
+                if let Some(cached) = SHADER_BINARY_CACHE.lock().get(&key) {
+                    if cached.source_hash == source_hash {
+                        log::trace!("Reusing the cached binary of {}", name);
+                        return Ok(CompiledShader {
+                            name,
+                            spirv: cached.spirv.clone(),
+                        });
+                    }
+                }
+
+                let spirv = compile_generic_shader_hlsl_impl(&name, &source, &key.profile)?;
+                SHADER_BINARY_CACHE.lock().insert(
+                    key,
+                    CachedShaderBinary {
+                        source_hash,
+                        spirv: spirv.clone(),
+                    },
+                );
 
                 Ok(CompiledShader { name, spirv })
             }
@@ -163,6 +222,16 @@ pub fn get_cs_local_size_from_spirv(spirv: &[u32]) -> Result<[u32; 3]> {
     Err(anyhow!("Could not find a ExecutionMode SPIR-V op"))
 }
 
+// Part of the hash of cached shader binaries, so changing these recompiles them.
+const DXC_ARGS: &[&str] = &[
+    "-spirv",
+    "-enable-templates",
+    //"-enable-16bit-types",
+    "-fspv-target-env=vulkan1.2",
+    "-WX",  // warnings as errors
+    "-Ges", // strict mode
+];
+
 fn compile_generic_shader_hlsl_impl(
     name: &str,
     source: &[shader_prepper::SourceChunk],
@@ -174,22 +243,8 @@ fn compile_generic_shader_hlsl_impl(
     }
 
     let t0 = std::time::Instant::now();
-    let spirv = hassle_rs::compile_hlsl(
-        name,
-        &source_text,
-        "main",
-        target_profile,
-        &[
-            "-spirv",
-            "-enable-templates",
-            //"-enable-16bit-types",
-            "-fspv-target-env=vulkan1.2",
-            "-WX",  // warnings as errors
-            "-Ges", // strict mode
-        ],
-        &[],
-    )
-    .map_err(|err| anyhow!("{}", err))?;
+    let spirv = hassle_rs::compile_hlsl(name, &source_text, "main", target_profile, DXC_ARGS, &[])
+        .map_err(|err| anyhow!("{}", err))?;
 
     log::trace!("dxc took {:?} for {}", t0.elapsed(), name,);
 
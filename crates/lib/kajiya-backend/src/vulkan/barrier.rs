@@ -8,14 +8,16 @@ pub struct ImageBarrier {
     prev_access: vk_sync::AccessType,
     next_access: vk_sync::AccessType,
     aspect_mask: vk::ImageAspectFlags,
+    base_mip_level: u32,
+    level_count: u32,
     discard: bool,
 }
 
 pub fn record_image_barrier(device: &Device, cb: vk::CommandBuffer, barrier: ImageBarrier) {
     let range = vk::ImageSubresourceRange {
         aspect_mask: barrier.aspect_mask,
-        base_mip_level: 0,
-        level_count: vk::REMAINING_MIP_LEVELS,
+        base_mip_level: barrier.base_mip_level,
+        level_count: barrier.level_count,
         base_array_layer: 0,
         layer_count: vk::REMAINING_ARRAY_LAYERS,
     };
@@ -40,6 +42,7 @@ pub fn record_image_barrier(device: &Device, cb: vk::CommandBuffer, barrier: Ima
 }
 
 impl ImageBarrier {
+    /// Covers all mips of the image; use `with_mip_range` to narrow it down.
     pub fn new(
         image: vk::Image,
         prev_access: vk_sync::AccessType,
@@ -52,15 +55,73 @@ impl ImageBarrier {
             next_access,
             discard: false,
             aspect_mask,
+            base_mip_level: 0,
+            level_count: vk::REMAINING_MIP_LEVELS,
         }
     }
 
+    pub fn with_mip_range(mut self, base_mip_level: u32, level_count: u32) -> Self {
+        self.base_mip_level = base_mip_level;
+        self.level_count = level_count;
+        self
+    }
+
     pub fn with_discard(mut self, discard: bool) -> Self {
         self.discard = discard;
         self
     }
 }
 
+pub struct BufferBarrier {
+    buffer: vk::Buffer,
+    prev_access: vk_sync::AccessType,
+    next_access: vk_sync::AccessType,
+    offset: usize,
+    size: usize,
+}
+
+pub fn record_buffer_barrier(device: &Device, cb: vk::CommandBuffer, barrier: BufferBarrier) {
+    vk_sync::cmd::pipeline_barrier(
+        device.raw.fp_v1_0(),
+        cb,
+        None,
+        &[vk_sync::BufferBarrier {
+            previous_accesses: &[barrier.prev_access],
+            next_accesses: &[barrier.next_access],
+            src_queue_family_index: device.universal_queue.family.index,
+            dst_queue_family_index: device.universal_queue.family.index,
+            buffer: barrier.buffer,
+            offset: barrier.offset,
+            size: barrier.size,
+        }],
+        &[],
+    );
+}
+
+impl BufferBarrier {
+    /// Covers the whole of the buffer; use `with_range` to narrow it down.
+    pub fn new(
+        buffer: vk::Buffer,
+        size: usize,
+        prev_access: vk_sync::AccessType,
+        next_access: vk_sync::AccessType,
+    ) -> Self {
+        Self {
+            buffer,
+            prev_access,
+            next_access,
+            offset: 0,
+            size,
+        }
+    }
+
+    pub fn with_range(mut self, offset: usize, size: usize) -> Self {
+        self.offset = offset;
+        self.size = size;
+        self
+    }
+}
+
 // From vk_sync
 pub struct AccessInfo {
     pub stage_mask: vk::PipelineStageFlags,
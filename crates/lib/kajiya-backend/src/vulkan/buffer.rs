@@ -131,6 +131,20 @@ impl Device {
         if initial_data.is_some() {
             desc.usage |= vk::BufferUsageFlags::TRANSFER_DST;
         }
+
+        if matches!(desc.memory_location, MemoryLocation::GpuOnly) {
+            // Estimated from the size; the exact requirements are only known
+            // once the buffer is created.
+            self.check_memory_budget(
+                &name,
+                &vk::MemoryRequirements {
+                    size: desc.size as u64,
+                    alignment: 1,
+                    memory_type_bits: !0,
+                },
+            )?;
+        }
+
         let buffer =
             Self::create_buffer_impl(&self.raw, &mut self.global_allocator.lock(), desc, &name)?;
 
@@ -166,6 +180,8 @@ impl Device {
     }
 
     pub fn immediate_destroy_buffer(&self, buffer: Buffer) {
+        self.invalidate_cached_descriptor_sets(buffer.raw);
+
         unsafe {
             self.raw.destroy_buffer(buffer.raw, None);
         }
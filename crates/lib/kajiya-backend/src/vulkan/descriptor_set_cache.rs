@@ -0,0 +1,148 @@
+use ash::vk;
+use std::collections::HashMap;
+
+// Entries not bound for this many frames are released, e.g. after shaders are reloaded,
+// or when transient resources get shuffled around.
+const MAX_UNUSED_FRAMES: u64 = 64;
+
+/// Contents of one binding of a cached descriptor set. Dynamic offsets aren't included,
+/// as they are provided when binding the set.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DescriptorSetCacheBinding {
+    Image {
+        view: vk::ImageView,
+        layout: vk::ImageLayout,
+    },
+    ImageArray(Vec<(vk::ImageView, vk::ImageLayout)>),
+    Buffer {
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize,
+    },
+    BufferArray(Vec<(vk::Buffer, vk::DeviceSize, vk::DeviceSize)>),
+    RayTracingAcceleration(vk::AccelerationStructureKHR),
+}
+
+impl DescriptorSetCacheBinding {
+    fn references_buffer(&self, raw: vk::Buffer) -> bool {
+        match self {
+            Self::Buffer { buffer, .. } => *buffer == raw,
+            Self::BufferArray(buffers) => buffers.iter().any(|(buffer, _, _)| *buffer == raw),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DescriptorSetCacheKey {
+    pub layout: vk::DescriptorSetLayout,
+    pub bindings: Vec<DescriptorSetCacheBinding>,
+}
+
+struct CachedDescriptorSet {
+    pool: vk::DescriptorPool,
+    set: vk::DescriptorSet,
+    last_used_frame: u64,
+}
+
+/// Descriptor sets which have already been allocated and written, keyed by their layout
+/// and binding contents. Sets are never updated after being cached, so they can be
+/// bound again in later frames while earlier ones are still in flight.
+///
+/// Entries referencing a buffer are dropped when it's destroyed. Images, their views,
+/// and acceleration structures are never destroyed by the backend, so their handles
+/// can't be reused by a different resource.
+pub(crate) struct DescriptorSetCache {
+    enabled: bool,
+    frame_index: u64,
+    sets: HashMap<DescriptorSetCacheKey, CachedDescriptorSet>,
+}
+
+impl Default for DescriptorSetCache {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            frame_index: 0,
+            sets: Default::default(),
+        }
+    }
+}
+
+impl DescriptorSetCache {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the pools of all cached sets, which the caller must release.
+    #[must_use]
+    pub(crate) fn set_enabled(&mut self, enabled: bool) -> Vec<vk::DescriptorPool> {
+        self.enabled = enabled;
+
+        if enabled {
+            Vec::new()
+        } else {
+            self.sets.drain().map(|(_, entry)| entry.pool).collect()
+        }
+    }
+
+    pub(crate) fn get(&mut self, key: &DescriptorSetCacheKey) -> Option<vk::DescriptorSet> {
+        let frame_index = self.frame_index;
+
+        self.sets.get_mut(key).map(|entry| {
+            entry.last_used_frame = frame_index;
+            entry.set
+        })
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        key: DescriptorSetCacheKey,
+        pool: vk::DescriptorPool,
+        set: vk::DescriptorSet,
+    ) {
+        self.sets.insert(
+            key,
+            CachedDescriptorSet {
+                pool,
+                set,
+                last_used_frame: self.frame_index,
+            },
+        );
+    }
+
+    /// Removes sets referencing `buffer`, returning their pools, which the caller must release.
+    #[must_use]
+    pub(crate) fn invalidate_buffer(&mut self, buffer: vk::Buffer) -> Vec<vk::DescriptorPool> {
+        self.remove_where(|key, _| {
+            key.bindings
+                .iter()
+                .any(|binding| binding.references_buffer(buffer))
+        })
+    }
+
+    /// Removes sets which haven't been used for a while, returning their pools,
+    /// which the caller must release.
+    #[must_use]
+    pub(crate) fn end_frame(&mut self) -> Vec<vk::DescriptorPool> {
+        self.frame_index += 1;
+
+        let frame_index = self.frame_index;
+        self.remove_where(|_, entry| frame_index - entry.last_used_frame > MAX_UNUSED_FRAMES)
+    }
+
+    fn remove_where(
+        &mut self,
+        pred: impl Fn(&DescriptorSetCacheKey, &CachedDescriptorSet) -> bool,
+    ) -> Vec<vk::DescriptorPool> {
+        let mut released = Vec::new();
+        self.sets.retain(|key, entry| {
+            if pred(key, entry) {
+                released.push(entry.pool);
+                false
+            } else {
+                true
+            }
+        });
+        released
+    }
+}
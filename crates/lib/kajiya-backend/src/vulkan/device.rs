@@ -3,6 +3,7 @@ use crate::{vulkan::buffer::BufferDesc, BackendError};
 pub use super::profiler::VkProfilerData;
 use super::{
     buffer::Buffer,
+    descriptor_set_cache::{DescriptorSetCache, DescriptorSetCacheKey},
     error::CrashMarkerNames,
     physical_device::{PhysicalDevice, QueueFamily},
     profiler::ProfilerBackend,
@@ -12,6 +13,7 @@ use ash::{
     extensions::{ext::DebugUtils, khr},
     vk,
 };
+use derive_builder::Builder;
 use gpu_allocator::{AllocatorDebugSettings, VulkanAllocator, VulkanAllocatorCreateDesc};
 use gpu_profiler::backend::ash::VulkanProfilerFrame;
 #[allow(unused_imports)]
@@ -28,6 +30,7 @@ use std::{
 /// in the same shader stage.
 pub const RESERVED_DESCRIPTOR_COUNT: u32 = 32;
 
+#[derive(Clone, Copy)]
 pub struct Queue {
     pub raw: vk::Queue,
     pub family: QueueFamily,
@@ -64,10 +67,102 @@ pub struct DeviceFrame {
     pub rendering_complete_semaphore: Option<vk::Semaphore>,
     pub main_command_buffer: CommandBuffer,
     pub presentation_command_buffer: CommandBuffer,
+    pub thread_command_pools: ThreadCommandPools,
     pub pending_resource_releases: Mutex<PendingResourceReleases>,
     pub profiler_data: VkProfilerData,
 }
 
+struct ThreadCommandPool {
+    raw: vk::CommandPool,
+    command_buffers: Vec<vk::CommandBuffer>,
+    next_free: usize,
+}
+
+/// Command pools for recording secondary command buffers on multiple threads.
+///
+/// Vulkan command pools must be externally synchronized, so each recording thread
+/// gets its own pool. Command buffers are recycled once the GPU is done with the frame.
+pub struct ThreadCommandPools {
+    queue_family_index: u32,
+    pools: Mutex<HashMap<std::thread::ThreadId, ThreadCommandPool>>,
+}
+
+impl ThreadCommandPools {
+    fn new(queue_family: &QueueFamily) -> Self {
+        Self {
+            queue_family_index: queue_family.index,
+            pools: Default::default(),
+        }
+    }
+
+    /// Returns a secondary command buffer from the calling thread's pool. It's valid until
+    /// the frame is recycled, and must only be recorded on the calling thread.
+    pub fn allocate_secondary(&self, device: &ash::Device) -> Result<vk::CommandBuffer> {
+        let mut pools = self.pools.lock();
+
+        let pool = match pools.entry(std::thread::current().id()) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let raw = unsafe {
+                    device.create_command_pool(
+                        &vk::CommandPoolCreateInfo::builder()
+                            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+                            .queue_family_index(self.queue_family_index),
+                        None,
+                    )
+                }?;
+
+                entry.insert(ThreadCommandPool {
+                    raw,
+                    command_buffers: Vec::new(),
+                    next_free: 0,
+                })
+            }
+        };
+
+        if pool.next_free == pool.command_buffers.len() {
+            let cb = unsafe {
+                device.allocate_command_buffers(
+                    &vk::CommandBufferAllocateInfo::builder()
+                        .command_buffer_count(1)
+                        .command_pool(pool.raw)
+                        .level(vk::CommandBufferLevel::SECONDARY),
+                )
+            }?[0];
+
+            pool.command_buffers.push(cb);
+        }
+
+        let cb = pool.command_buffers[pool.next_free];
+        pool.next_free += 1;
+
+        Ok(cb)
+    }
+
+    // Must only be called once the GPU is done executing the frame's command buffers.
+    fn reset(&mut self, device: &ash::Device) {
+        for pool in self.pools.get_mut().values_mut() {
+            if pool.next_free > 0 {
+                unsafe {
+                    device
+                        .reset_command_pool(pool.raw, vk::CommandPoolResetFlags::empty())
+                        .expect("reset_command_pool");
+                }
+                pool.next_free = 0;
+            }
+        }
+    }
+
+    // Must only be called once the GPU is idle.
+    fn destroy(&self, device: &ash::Device) {
+        for (_, pool) in self.pools.lock().drain() {
+            unsafe {
+                device.destroy_command_pool(pool.raw, None);
+            }
+        }
+    }
+}
+
 pub struct CommandBuffer {
     pub raw: vk::CommandBuffer,
     pub submit_done_fence: vk::Fence,
@@ -129,6 +224,7 @@ impl DeviceFrame {
             rendering_complete_semaphore: None,
             main_command_buffer: CommandBuffer::new(device, queue_family).unwrap(),
             presentation_command_buffer: CommandBuffer::new(device, queue_family).unwrap(),
+            thread_command_pools: ThreadCommandPools::new(queue_family),
             pending_resource_releases: Default::default(),
             profiler_data: VulkanProfilerFrame::new(
                 device,
@@ -147,8 +243,15 @@ pub struct Device {
     pub(crate) pdevice: Arc<PhysicalDevice>,
     pub(crate) instance: Arc<super::instance::Instance>,
     pub universal_queue: Queue,
+    // Same as `universal_queue` unless the universal queue family can't present
+    pub(crate) presentation_queue: Queue,
     pub(crate) global_allocator: Arc<Mutex<VulkanAllocator>>,
-    pub(crate) immutable_samplers: HashMap<SamplerDesc, vk::Sampler>,
+    pub(crate) immutable_samplers: Mutex<HashMap<SamplerDesc, vk::Sampler>>,
+
+    // Zero if the `samplerAnisotropy` feature is not supported.
+    max_sampler_anisotropy: u32,
+    depth_format: vk::Format,
+    hdr_color_attachment_format: vk::Format,
     pub(crate) setup_cb: Mutex<CommandBuffer>,
 
     pub(crate) crash_tracking_buffer: Buffer,
@@ -162,6 +265,14 @@ pub struct Device {
     frames: [Mutex<Arc<DeviceFrame>>; 2],
 
     ray_tracing_enabled: bool,
+
+    memory_budget_ext_enabled: bool,
+    memory_budget: Mutex<Option<u64>>,
+
+    descriptor_set_cache: Mutex<DescriptorSetCache>,
+
+    // Used for creating all pipelines; see `pipeline_cache_data`.
+    pub(crate) raw_pipeline_cache: vk::PipelineCache,
 }
 
 // Allowing `Send` on `frames` is technically unsound. There are some checks
@@ -251,6 +362,13 @@ impl Device {
             device_extension_names.push(khr::Swapchain::name().as_ptr());
         }
 
+        // Optional; used to check allocations against the memory budget.
+        let memory_budget_ext_enabled =
+            supported_extensions.contains(vk::ExtMemoryBudgetFn::name().to_string_lossy().as_ref());
+        if memory_budget_ext_enabled {
+            device_extension_names.push(vk::ExtMemoryBudgetFn::name().as_ptr());
+        }
+
         unsafe {
             for &ext in &device_extension_names {
                 let ext = std::ffi::CStr::from_ptr(ext).to_string_lossy();
@@ -262,24 +380,57 @@ impl Device {
 
         let priorities = [1.0];
 
+        let universal_queue_flags = vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE;
+        let presentation_queue_family = pdevice.presentation_queue_family;
+
+        // Prefer a universal queue family which can also present
         let universal_queue = pdevice
             .queue_families
             .iter()
-            .filter(|qf| qf.properties.queue_flags.contains(vk::QueueFlags::GRAPHICS))
-            .copied()
-            .next();
+            .filter(|qf| qf.properties.queue_flags.contains(universal_queue_flags))
+            .min_by_key(|qf| {
+                presentation_queue_family.map_or(false, |present| present.index != qf.index)
+            })
+            .copied();
 
         let universal_queue = if let Some(universal_queue) = universal_queue {
             universal_queue
         } else {
-            anyhow::bail!("No suitable render queue found");
+            anyhow::bail!(
+                "No queue family supports both graphics and compute. Available families: {:?}",
+                pdevice
+                    .queue_families
+                    .iter()
+                    .map(|qf| qf.properties.queue_flags)
+                    .collect::<Vec<_>>()
+            );
         };
 
-        let universal_queue_info = [vk::DeviceQueueCreateInfo::builder()
+        let presentation_queue_family = match presentation_queue_family {
+            Some(present) if present.index != universal_queue.index => {
+                info!(
+                    "Using a separate presentation queue family ({}) from the universal one ({})",
+                    present.index, universal_queue.index
+                );
+                present
+            }
+            _ => universal_queue,
+        };
+
+        let mut universal_queue_info = vec![vk::DeviceQueueCreateInfo::builder()
             .queue_family_index(universal_queue.index)
             .queue_priorities(&priorities)
             .build()];
 
+        if presentation_queue_family.index != universal_queue.index {
+            universal_queue_info.push(
+                vk::DeviceQueueCreateInfo::builder()
+                    .queue_family_index(presentation_queue_family.index)
+                    .queue_priorities(&priorities)
+                    .build(),
+            );
+        }
+
         let mut scalar_block = vk::PhysicalDeviceScalarBlockLayoutFeaturesEXT::default();
         let mut descriptor_indexing = vk::PhysicalDeviceDescriptorIndexingFeaturesEXT::default();
         let mut imageless_framebuffer =
@@ -374,6 +525,7 @@ impl Device {
                 .unwrap();
 
             info!("Created a Vulkan device");
+            info!("Subgroup properties: {:?}", pdevice.subgroup_properties);
 
             let mut global_allocator = VulkanAllocator::new(&VulkanAllocatorCreateDesc {
                 instance: instance.clone(),
@@ -393,6 +545,11 @@ impl Device {
                 family: universal_queue,
             };
 
+            let presentation_queue = Queue {
+                raw: device.get_device_queue(presentation_queue_family.index, 0),
+                family: presentation_queue_family,
+            };
+
             let frame0 = DeviceFrame::new(
                 pdevice,
                 &device,
@@ -407,9 +564,26 @@ impl Device {
             );
             //let frame2 = DeviceFrame::new(&device, &mut global_allocator, &universal_queue.family);
 
-            let immutable_samplers = Self::create_samplers(&device);
+            let max_sampler_anisotropy = if features2.features.sampler_anisotropy != 0 {
+                pdevice.properties.limits.max_sampler_anisotropy as u32
+            } else {
+                warn!("samplerAnisotropy not supported. Anisotropic filtering will be disabled.");
+                0
+            };
+            let depth_format = Self::select_depth_format(pdevice)?;
+            info!("Using depth format {:?}", depth_format);
+            let hdr_color_attachment_format = Self::select_hdr_color_attachment_format(pdevice)?;
+            info!(
+                "Using HDR color attachment format {:?}",
+                hdr_color_attachment_format
+            );
+
             let setup_cb = CommandBuffer::new(&device, &universal_queue.family).unwrap();
 
+            let raw_pipeline_cache = unsafe {
+                device.create_pipeline_cache(&vk::PipelineCacheCreateInfo::default(), None)?
+            };
+
             let acceleration_structure_ext =
                 khr::AccelerationStructure::new(&pdevice.instance.raw, &device);
             let ray_tracing_pipeline_ext =
@@ -430,8 +604,12 @@ impl Device {
                 instance: pdevice.instance.clone(),
                 raw: device,
                 universal_queue,
+                presentation_queue,
                 global_allocator: Arc::new(Mutex::new(global_allocator)),
-                immutable_samplers,
+                immutable_samplers: Default::default(),
+                max_sampler_anisotropy,
+                depth_format,
+                hdr_color_attachment_format,
                 setup_cb: Mutex::new(setup_cb),
                 crash_tracking_buffer,
                 crash_marker_names: Default::default(),
@@ -445,64 +623,57 @@ impl Device {
                     //Mutex::new(Arc::new(frame2)),
                 ],
                 ray_tracing_enabled,
+                memory_budget_ext_enabled,
+                memory_budget: Default::default(),
+                descriptor_set_cache: Default::default(),
+                raw_pipeline_cache,
             }))
         }
     }
 
-    fn create_samplers(device: &ash::Device) -> HashMap<SamplerDesc, vk::Sampler> {
-        let texel_filters = [vk::Filter::NEAREST, vk::Filter::LINEAR];
-        let mipmap_modes = [
-            vk::SamplerMipmapMode::NEAREST,
-            vk::SamplerMipmapMode::LINEAR,
-        ];
-        let address_modes = [
-            vk::SamplerAddressMode::REPEAT,
-            vk::SamplerAddressMode::CLAMP_TO_EDGE,
-        ];
+    fn create_sampler(&self, desc: SamplerDesc) -> vk::Sampler {
+        let anisotropy_enable = desc.max_anisotropy > 1;
 
-        let mut result = HashMap::new();
-
-        for &texel_filter in &texel_filters {
-            for &mipmap_mode in &mipmap_modes {
-                for &address_modes in &address_modes {
-                    let anisotropy_enable = texel_filter == vk::Filter::LINEAR;
-
-                    result.insert(
-                        SamplerDesc {
-                            texel_filter,
-                            mipmap_mode,
-                            address_modes,
-                        },
-                        unsafe {
-                            device.create_sampler(
-                                &vk::SamplerCreateInfo::builder()
-                                    .mag_filter(texel_filter)
-                                    .min_filter(texel_filter)
-                                    .mipmap_mode(mipmap_mode)
-                                    .address_mode_u(address_modes)
-                                    .address_mode_v(address_modes)
-                                    .address_mode_w(address_modes)
-                                    .max_lod(vk::LOD_CLAMP_NONE)
-                                    .max_anisotropy(16.0)
-                                    .anisotropy_enable(anisotropy_enable)
-                                    .build(),
-                                None,
-                            )
-                        }
-                        .expect("create_sampler"),
-                    );
-                }
-            }
+        unsafe {
+            self.raw.create_sampler(
+                &vk::SamplerCreateInfo::builder()
+                    .mag_filter(desc.texel_filter)
+                    .min_filter(desc.texel_filter)
+                    .mipmap_mode(desc.mipmap_mode)
+                    .address_mode_u(desc.address_modes)
+                    .address_mode_v(desc.address_modes)
+                    .address_mode_w(desc.address_modes)
+                    .max_lod(vk::LOD_CLAMP_NONE)
+                    .max_anisotropy(desc.max_anisotropy.max(1) as f32)
+                    .anisotropy_enable(anisotropy_enable)
+                    .build(),
+                None,
+            )
         }
-
-        result
+        .expect("create_sampler")
     }
 
-    pub fn get_sampler(&self, desc: SamplerDesc) -> vk::Sampler {
+    /// Returns a sampler matching `desc`, creating it on first use.
+    ///
+    /// Anisotropy is clamped to the device's `maxSamplerAnisotropy`, and disabled
+    /// if the device doesn't support `samplerAnisotropy`.
+    pub fn get_sampler(&self, mut desc: SamplerDesc) -> vk::Sampler {
+        desc.max_anisotropy = if self.max_sampler_anisotropy > 1 {
+            desc.max_anisotropy.min(self.max_sampler_anisotropy)
+        } else {
+            0
+        };
+
+        // Anisotropy only makes sense for linear filtering; don't create redundant samplers.
+        if desc.texel_filter != vk::Filter::LINEAR || desc.max_anisotropy == 1 {
+            desc.max_anisotropy = 0;
+        }
+
         *self
             .immutable_samplers
-            .get(&desc)
-            .unwrap_or_else(|| panic!("Sampler not found: {:?}", desc))
+            .lock()
+            .entry(desc)
+            .or_insert_with(|| self.create_sampler(desc))
     }
 
     pub fn begin_frame(&self) -> Arc<DeviceFrame> {
@@ -537,6 +708,8 @@ impl Device {
                     .expect("Wait for fence failed.");
             }
 
+            frame0.thread_command_pools.reset(&self.raw);
+
             puffin::profile_scope!("release pending resources");
             frame0
                 .pending_resource_releases
@@ -547,10 +720,184 @@ impl Device {
         frame0.clone()
     }
 
+    /// Waits for the GPU to finish the most recently submitted frame. `begin_frame` only waits
+    /// for the one before it, letting the CPU run a frame ahead; this removes that slack.
+    pub fn wait_for_previous_frame(&self) {
+        let frame1 = self.frames[1].lock();
+
+        unsafe {
+            puffin::profile_scope!("wait previous frame");
+
+            self.raw
+                .wait_for_fences(
+                    &[
+                        frame1.main_command_buffer.submit_done_fence,
+                        frame1.presentation_command_buffer.submit_done_fence,
+                    ],
+                    true,
+                    std::u64::MAX,
+                )
+                .map_err(|err| self.report_error(err.into()))
+                .expect("Wait for fence failed.");
+        }
+    }
+
+    /// Whether images of `format` can be sampled with optimal tiling.
+    pub fn supports_sampled_format(&self, format: vk::Format) -> bool {
+        let properties = unsafe {
+            self.instance
+                .raw
+                .get_physical_device_format_properties(self.pdevice.raw, format)
+        };
+
+        properties
+            .optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE)
+    }
+
+    /// Limits the device-local memory used by the application. Once set, `create_image`
+    /// and `create_buffer` return `BackendError::OverBudget` for GPU-only allocations
+    /// which would exceed it, or the budget reported by the driver, whichever is lower.
+    ///
+    /// Current usage is only known with `VK_EXT_memory_budget`. Without it, only single
+    /// allocations larger than the budget or the memory heap are refused.
+    pub fn set_memory_budget(&self, budget_bytes: Option<u64>) {
+        *self.memory_budget.lock() = budget_bytes;
+    }
+
+    pub(crate) fn check_memory_budget(
+        &self,
+        name: &str,
+        requirements: &vk::MemoryRequirements,
+    ) -> Result<(), BackendError> {
+        let budget = if let Some(budget) = *self.memory_budget.lock() {
+            budget
+        } else {
+            return Ok(());
+        };
+
+        let memory_properties = &self.pdevice.memory_properties;
+        let heap_index = if let Some(memory_type) = memory_properties.memory_types
+            [..memory_properties.memory_type_count as usize]
+            .iter()
+            .enumerate()
+            .find(|(i, memory_type)| {
+                (requirements.memory_type_bits & (1 << i)) != 0
+                    && memory_type
+                        .property_flags
+                        .contains(vk::MemoryPropertyFlags::DEVICE_LOCAL)
+            })
+            .map(|(_, memory_type)| memory_type)
+        {
+            memory_type.heap_index as usize
+        } else {
+            return Ok(());
+        };
+
+        let (heap_usage, heap_budget) = if self.memory_budget_ext_enabled {
+            let mut budget_properties = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+            let mut properties = vk::PhysicalDeviceMemoryProperties2::builder()
+                .push_next(&mut budget_properties)
+                .build();
+            unsafe {
+                self.instance
+                    .raw
+                    .get_physical_device_memory_properties2(self.pdevice.raw, &mut properties)
+            };
+
+            (
+                budget_properties.heap_usage[heap_index],
+                budget_properties.heap_budget[heap_index],
+            )
+        } else {
+            (0, memory_properties.memory_heaps[heap_index].size)
+        };
+
+        let available = budget.min(heap_budget).saturating_sub(heap_usage);
+        if requirements.size > available {
+            Err(BackendError::OverBudget {
+                name: name.to_owned(),
+                size: requirements.size,
+                available,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn defer_release(&self, resource: impl DeferredRelease) {
         resource.enqueue_release(&mut self.frames[0].lock().pending_resource_releases.lock());
     }
 
+    /// Enables or disables reuse of descriptor sets with identical bindings across
+    /// passes and frames. Enabled by default; disabling it releases all cached sets.
+    pub fn set_descriptor_set_caching(&self, enabled: bool) {
+        let released = self.descriptor_set_cache.lock().set_enabled(enabled);
+        for pool in released {
+            self.defer_release(pool);
+        }
+    }
+
+    pub fn descriptor_set_caching_enabled(&self) -> bool {
+        self.descriptor_set_cache.lock().is_enabled()
+    }
+
+    /// Returns a descriptor set previously written with the same layout and bindings.
+    pub fn cached_descriptor_set(&self, key: &DescriptorSetCacheKey) -> Option<vk::DescriptorSet> {
+        self.descriptor_set_cache.lock().get(key)
+    }
+
+    /// Keeps `set` around for reuse with the same layout and bindings. The set must have been
+    /// allocated from `pool`, which is owned by the cache from now on, and must not be
+    /// written to again. Without caching enabled, the pool is released at the end of the frame.
+    pub fn cache_descriptor_set(
+        &self,
+        key: DescriptorSetCacheKey,
+        pool: vk::DescriptorPool,
+        set: vk::DescriptorSet,
+    ) {
+        let mut cache = self.descriptor_set_cache.lock();
+        if cache.is_enabled() {
+            cache.insert(key, pool, set);
+        } else {
+            drop(cache);
+            self.defer_release(pool);
+        }
+    }
+
+    /// Contents of the driver's pipeline cache, to be passed to `merge_pipeline_cache_data`
+    /// in a later run. The data starts with a header identifying the device and driver.
+    pub fn pipeline_cache_data(&self) -> Result<Vec<u8>> {
+        Ok(unsafe { self.raw.get_pipeline_cache_data(self.raw_pipeline_cache)? })
+    }
+
+    /// Adds data from `pipeline_cache_data` to the driver's pipeline cache. Data from
+    /// a different device or driver version is ignored by the driver.
+    ///
+    /// Must not be called while pipelines are being created.
+    pub fn merge_pipeline_cache_data(&self, data: &[u8]) -> Result<()> {
+        unsafe {
+            let loaded = self.raw.create_pipeline_cache(
+                &vk::PipelineCacheCreateInfo::builder().initial_data(data),
+                None,
+            )?;
+            let result = self
+                .raw
+                .merge_pipeline_caches(self.raw_pipeline_cache, &[loaded]);
+            self.raw.destroy_pipeline_cache(loaded, None);
+            result?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn invalidate_cached_descriptor_sets(&self, buffer: vk::Buffer) {
+        let released = self.descriptor_set_cache.lock().invalidate_buffer(buffer);
+        for pool in released {
+            self.defer_release(pool);
+        }
+    }
+
     pub fn with_setup_cb(
         &self,
         callback: impl FnOnce(vk::CommandBuffer),
@@ -592,6 +939,10 @@ impl Device {
     pub fn finish_frame(&self, frame: Arc<DeviceFrame>) {
         drop(frame);
 
+        for pool in self.descriptor_set_cache.lock().end_frame() {
+            self.defer_release(pool);
+        }
+
         let mut frame0 = self.frames[0].lock();
         let frame0: &mut DeviceFrame = Arc::get_mut(&mut frame0).unwrap_or_else(|| {
             panic!("Unable to finish frame: frame data is being held by user code")
@@ -609,6 +960,89 @@ impl Device {
         }
     }
 
+    fn select_depth_format(pdevice: &PhysicalDevice) -> Result<vk::Format> {
+        // In order of preference. Reverse-Z needs float depth for its precision benefits.
+        const DEPTH_FORMATS: [vk::Format; 4] = [
+            vk::Format::D32_SFLOAT,
+            vk::Format::D32_SFLOAT_S8_UINT,
+            vk::Format::X8_D24_UNORM_PACK32,
+            vk::Format::D24_UNORM_S8_UINT,
+        ];
+
+        // Depth gets sampled by lighting passes in addition to being rendered to
+        let required_features = vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT
+            | vk::FormatFeatureFlags::SAMPLED_IMAGE;
+
+        DEPTH_FORMATS
+            .iter()
+            .copied()
+            .find(|&format| {
+                let properties = unsafe {
+                    pdevice
+                        .instance
+                        .raw
+                        .get_physical_device_format_properties(pdevice.raw, format)
+                };
+                properties
+                    .optimal_tiling_features
+                    .contains(required_features)
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "None of the depth formats {:?} support {:?}",
+                    DEPTH_FORMATS,
+                    required_features
+                )
+            })
+    }
+
+    /// The format to use for depth buffers, selected at device creation.
+    pub fn depth_format(&self) -> vk::Format {
+        self.depth_format
+    }
+
+    fn select_hdr_color_attachment_format(pdevice: &PhysicalDevice) -> Result<vk::Format> {
+        // In order of preference. Only signed, unbounded formats will do: velocities
+        // are rendered as view-space position deltas, which can be negative or exceed one.
+        const COLOR_FORMATS: [vk::Format; 2] = [
+            vk::Format::R16G16B16A16_SFLOAT,
+            vk::Format::R32G32B32A32_SFLOAT,
+        ];
+
+        // Rendered to without blending, and sampled by subsequent passes
+        let required_features =
+            vk::FormatFeatureFlags::COLOR_ATTACHMENT | vk::FormatFeatureFlags::SAMPLED_IMAGE;
+
+        COLOR_FORMATS
+            .iter()
+            .copied()
+            .find(|&format| {
+                let properties = unsafe {
+                    pdevice
+                        .instance
+                        .raw
+                        .get_physical_device_format_properties(pdevice.raw, format)
+                };
+                properties
+                    .optimal_tiling_features
+                    .contains(required_features)
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "None of the color formats {:?} support {:?}",
+                    COLOR_FORMATS,
+                    required_features
+                )
+            })
+    }
+
+    /// The format to use for HDR color attachments of raster passes, selected at device
+    /// creation. Always a signed floating-point one, so it can hold velocities.
+    /// Images rendered to by such passes must be created with this format too.
+    pub fn hdr_color_attachment_format(&self) -> vk::Format {
+        self.hdr_color_attachment_format
+    }
+
     pub fn physical_device(&self) -> &PhysicalDevice {
         self.pdevice.as_ref()
     }
@@ -638,6 +1072,10 @@ impl Drop for Device {
             log::trace!("device_wait_idle");
             let _ = self.raw.device_wait_idle();
         }
+
+        for frame in &self.frames {
+            frame.lock().thread_command_pools.destroy(&self.raw);
+        }
     }
 }
 
@@ -664,9 +1102,23 @@ impl Drop for Device {
     }
 }*/
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug, Builder)]
+#[builder(pattern = "owned", derive(Clone))]
 pub struct SamplerDesc {
+    #[builder(default = "vk::Filter::LINEAR")]
     pub texel_filter: vk::Filter,
+    #[builder(default = "vk::SamplerMipmapMode::LINEAR")]
     pub mipmap_mode: vk::SamplerMipmapMode,
+    #[builder(default = "vk::SamplerAddressMode::REPEAT")]
     pub address_modes: vk::SamplerAddressMode,
+    /// Values of 0 and 1 disable anisotropic filtering.
+    #[builder(default = "0")]
+    pub max_anisotropy: u32,
+}
+
+impl SamplerDesc {
+    /// Defaults to trilinear filtering with repeat addressing and no anisotropy.
+    pub fn builder() -> SamplerDescBuilder {
+        Default::default()
+    }
 }
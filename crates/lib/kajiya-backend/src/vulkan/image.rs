@@ -2,7 +2,10 @@
 
 use crate::BackendError;
 
-use super::device::Device;
+use super::{
+    barrier::{image_aspect_mask_from_format, record_image_barrier, ImageBarrier},
+    device::{CommandBuffer, Device},
+};
 use ash::vk;
 use derive_builder::Builder;
 use gpu_allocator::{AllocationCreateDesc, MemoryLocation};
@@ -272,6 +275,14 @@ impl Device {
         };
         let requirements = unsafe { self.raw.get_image_memory_requirements(image) };
 
+        if let Err(err) = self.check_memory_budget(
+            &format!("{:?} {:?} image", desc.extent, desc.format),
+            &requirements,
+        ) {
+            unsafe { self.raw.destroy_image(image, None) };
+            return Err(err);
+        }
+
         let allocation = self
             .global_allocator
             .lock()
@@ -331,6 +342,8 @@ impl Device {
                     mapped_slice_mut[offset..offset + sub.data.len()].copy_from_slice(sub.data);
                     assert_eq!(offset % block_bytes, 0);
 
+                    // Mips are tightly packed. For block-compressed formats, the extent
+                    // of mips smaller than a block is still in texels, and matches the subresource.
                     let region = vk::BufferImageCopy::builder()
                         .buffer_offset(offset as _)
                         .image_subresource(
@@ -410,13 +423,130 @@ impl Device {
         })
     }
 
+    /// Fills mips `1..` of `image` by successively downsampling the previous level,
+    /// starting from the contents of mip 0. Applies to all array layers.
+    ///
+    /// The image must have been created with `TRANSFER_SRC` and `TRANSFER_DST` usage,
+    /// and is expected to be ready for sampling, as left by `create_image` with initial data
+    /// for mip 0. It's ready for sampling again once the command buffer is done.
+    pub fn generate_mips(&self, cb: &CommandBuffer, image: &Image) {
+        let desc = &image.desc;
+        let mip_levels = desc.mip_levels as u32;
+        if mip_levels <= 1 {
+            return;
+        }
+
+        let aspect_mask = image_aspect_mask_from_format(desc.format);
+        let sampled = vk_sync::AccessType::AnyShaderReadSampledImageOrUniformTexelBuffer;
+
+        let format_properties = unsafe {
+            self.pdevice
+                .instance
+                .raw
+                .get_physical_device_format_properties(self.pdevice.raw, desc.format)
+        };
+        let filter = if format_properties
+            .optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
+        {
+            vk::Filter::LINEAR
+        } else {
+            vk::Filter::NEAREST
+        };
+
+        let mip_extent = |level: u32| vk::Offset3D {
+            x: (desc.extent[0] >> level).max(1) as i32,
+            y: (desc.extent[1] >> level).max(1) as i32,
+            z: (desc.extent[2] >> level).max(1) as i32,
+        };
+        let subresource = |level: u32| vk::ImageSubresourceLayers {
+            aspect_mask,
+            mip_level: level,
+            base_array_layer: 0,
+            layer_count: desc.array_elements,
+        };
+
+        for level in 1..mip_levels {
+            // The source was either sampled (mip 0), or just blitted to.
+            let src_prev_access = if level == 1 {
+                sampled
+            } else {
+                vk_sync::AccessType::TransferWrite
+            };
+
+            record_image_barrier(
+                self,
+                cb.raw,
+                ImageBarrier::new(
+                    image.raw,
+                    src_prev_access,
+                    vk_sync::AccessType::TransferRead,
+                    aspect_mask,
+                )
+                .with_mip_range(level - 1, 1),
+            );
+            record_image_barrier(
+                self,
+                cb.raw,
+                ImageBarrier::new(
+                    image.raw,
+                    sampled,
+                    vk_sync::AccessType::TransferWrite,
+                    aspect_mask,
+                )
+                .with_mip_range(level, 1)
+                .with_discard(true),
+            );
+
+            unsafe {
+                self.raw.cmd_blit_image(
+                    cb.raw,
+                    image.raw,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image.raw,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[vk::ImageBlit {
+                        src_subresource: subresource(level - 1),
+                        src_offsets: [vk::Offset3D::default(), mip_extent(level - 1)],
+                        dst_subresource: subresource(level),
+                        dst_offsets: [vk::Offset3D::default(), mip_extent(level)],
+                    }],
+                    filter,
+                );
+            }
+        }
+
+        record_image_barrier(
+            self,
+            cb.raw,
+            ImageBarrier::new(
+                image.raw,
+                vk_sync::AccessType::TransferRead,
+                sampled,
+                aspect_mask,
+            )
+            .with_mip_range(0, mip_levels - 1),
+        );
+        record_image_barrier(
+            self,
+            cb.raw,
+            ImageBarrier::new(
+                image.raw,
+                vk_sync::AccessType::TransferWrite,
+                sampled,
+                aspect_mask,
+            )
+            .with_mip_range(mip_levels - 1, 1),
+        );
+    }
+
     fn create_image_view(
         &self,
         desc: ImageViewDesc,
         image_desc: &ImageDesc,
         image_raw: vk::Image,
     ) -> Result<vk::ImageView, BackendError> {
-        if image_desc.format == vk::Format::D32_SFLOAT
+        if image_aspect_mask_from_format(image_desc.format).contains(vk::ImageAspectFlags::DEPTH)
             && !desc.aspect_mask.contains(vk::ImageAspectFlags::DEPTH)
         {
             return Err(BackendError::ResourceAccess {
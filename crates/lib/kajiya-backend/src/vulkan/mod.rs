@@ -1,5 +1,6 @@
 pub mod barrier;
 pub mod buffer;
+pub mod descriptor_set_cache;
 pub mod device;
 pub mod error;
 pub mod image;
@@ -18,16 +19,21 @@ use raw_window_handle::HasRawWindowHandle;
 use std::sync::Arc;
 
 fn select_surface_format(formats: Vec<vk::SurfaceFormatKHR>) -> Option<vk::SurfaceFormatKHR> {
-    let preferred = vk::SurfaceFormatKHR {
-        format: vk::Format::B8G8R8A8_UNORM,
-        color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
-    };
-
-    if formats.contains(&preferred) {
-        Some(preferred)
-    } else {
-        None
-    }
+    // sRGB formats can't be written to from compute, and need the blit present path.
+    let preferred = [
+        vk::Format::B8G8R8A8_UNORM,
+        vk::Format::R8G8B8A8_UNORM,
+        vk::Format::B8G8R8A8_SRGB,
+        vk::Format::R8G8B8A8_SRGB,
+    ];
+
+    preferred.iter().find_map(|&format| {
+        let format = vk::SurfaceFormatKHR {
+            format,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        };
+        formats.contains(&format).then(|| format)
+    })
 }
 
 pub struct RenderBackend {
@@ -40,6 +46,8 @@ pub struct RenderBackend {
 pub struct RenderBackendConfig {
     pub swapchain_extent: [u32; 2],
     pub vsync: bool,
+    pub desired_swapchain_image_count: u32,
+    pub swapchain_present_path: Option<swapchain::SwapchainPresentPath>,
     pub graphics_debugging: bool,
     pub device_index: Option<usize>,
 }
@@ -71,8 +79,24 @@ impl RenderBackend {
                 .collect::<Vec<_>>()
         );
 
+        if physical_devices.is_empty() {
+            anyhow::bail!(
+                "No physical device has a queue family capable of presenting to the window surface"
+            );
+        }
+
         let physical_device = Arc::new(if let Some(device_index) = config.device_index {
-            physical_devices.into_iter().nth(device_index).unwrap()
+            let device_count = physical_devices.len();
+            physical_devices
+                .into_iter()
+                .nth(device_index)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Requested device index {} is out of range; {} suitable device(s) found",
+                        device_index,
+                        device_count
+                    )
+                })?
         } else {
             physical_devices
                 .into_iter()
@@ -105,6 +129,8 @@ impl RenderBackend {
                     height: config.swapchain_extent[1],
                 },
                 vsync: config.vsync,
+                desired_image_count: config.desired_swapchain_image_count,
+                preferred_present_path: config.swapchain_present_path,
             },
         )?;
 
@@ -115,6 +141,68 @@ impl RenderBackend {
         })
     }
 
+    /// Replaces a lost surface (`ERROR_SURFACE_LOST_KHR`) with a new one for `window`,
+    /// and rebuilds the swapchain with the same settings.
+    ///
+    /// Fails if the new surface can't be presented to by the device, in which case
+    /// the backend can't render to the window any more.
+    pub fn recreate_surface(&mut self, window: &impl HasRawWindowHandle) -> anyhow::Result<()> {
+        unsafe { self.device.raw.device_wait_idle() }?;
+
+        let surface = surface::Surface::create(&self.device.instance, window)?;
+
+        let presentation_supported = unsafe {
+            surface.fns.get_physical_device_surface_support(
+                self.device.pdevice.raw,
+                self.device.presentation_queue.family.index,
+                surface.raw,
+            )
+        }?;
+        anyhow::ensure!(
+            presentation_supported,
+            "The recreated surface does not support presentation from the device's queue"
+        );
+
+        let mut desc = self.swapchain.desc;
+        let surface_formats =
+            swapchain::Swapchain::enumerate_surface_formats(&self.device, &surface)?;
+        if !surface_formats.contains(&desc.format) {
+            desc.format = select_surface_format(surface_formats)
+                .ok_or_else(|| anyhow::anyhow!("No suitable format for the recreated surface"))?;
+        }
+        desc.preferred_present_path = Some(self.swapchain.present_path);
+
+        let swapchain = swapchain::Swapchain::new(&self.device, &surface, desc)?;
+
+        // The old swapchain goes first, then the surface it was created for.
+        let old_surface = std::mem::replace(&mut self.surface, surface);
+        self.swapchain = swapchain;
+        unsafe { old_surface.fns.destroy_surface(old_surface.raw, None) };
+
+        info!("Recreated the window surface");
+
+        Ok(())
+    }
+
+    /// Rebuilds the swapchain for the same surface with a new extent, e.g. when acquiring
+    /// an image fails with `ERROR_OUT_OF_DATE_KHR`.
+    pub fn recreate_swapchain(&mut self, extent: [u32; 2]) -> anyhow::Result<()> {
+        unsafe { self.device.raw.device_wait_idle() }?;
+
+        // The old swapchain is retired by creating the new one, and destroyed after.
+        self.swapchain = self.swapchain.recreate(vk::Extent2D {
+            width: extent[0],
+            height: extent[1],
+        })?;
+
+        info!(
+            "Recreated the swapchain at {}x{}",
+            self.swapchain.desc.dims.width, self.swapchain.desc.dims.height
+        );
+
+        Ok(())
+    }
+
     /*fn maintain(&mut self) {
         self.images.maintain();
     }*/
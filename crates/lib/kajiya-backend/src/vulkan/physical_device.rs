@@ -25,13 +25,34 @@ pub struct QueueFamily {
     pub properties: vk::QueueFamilyProperties,
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct SubgroupProperties {
+    pub subgroup_size: u32,
+    pub supported_stages: vk::ShaderStageFlags,
+    pub supported_operations: vk::SubgroupFeatureFlags,
+}
+
+impl SubgroupProperties {
+    /// Whether compute shaders can use wave reductions such as `WaveActiveSum`.
+    pub fn supports_compute_arithmetic(&self) -> bool {
+        self.supported_stages
+            .contains(vk::ShaderStageFlags::COMPUTE)
+            && self
+                .supported_operations
+                .contains(vk::SubgroupFeatureFlags::BASIC | vk::SubgroupFeatureFlags::ARITHMETIC)
+    }
+}
+
 pub struct PhysicalDevice {
     pub instance: Arc<Instance>,
     pub raw: vk::PhysicalDevice,
     pub(crate) queue_families: Vec<QueueFamily>,
     pub(crate) presentation_requested: bool,
+    // Preferred queue family for presentation, if presentation was requested
+    pub(crate) presentation_queue_family: Option<QueueFamily>,
     pub properties: PhysicalDeviceProperties,
     pub memory_properties: PhysicalDeviceMemoryProperties,
+    pub subgroup_properties: SubgroupProperties,
 }
 
 impl std::fmt::Debug for PhysicalDevice {
@@ -76,13 +97,33 @@ pub fn enumerate_physical_devices(instance: &Arc<Instance>) -> Result<Vec<Physic
 
                 let memory_properties = instance.raw.get_physical_device_memory_properties(pdevice);
 
+                let subgroup_properties = {
+                    let mut subgroup = vk::PhysicalDeviceSubgroupProperties::default();
+                    let mut properties2 = vk::PhysicalDeviceProperties2::builder()
+                        .push_next(&mut subgroup)
+                        .build();
+
+                    instance
+                        .raw
+                        .fp_v1_1()
+                        .get_physical_device_properties2(pdevice, &mut properties2);
+
+                    SubgroupProperties {
+                        subgroup_size: subgroup.subgroup_size,
+                        supported_stages: subgroup.supported_stages,
+                        supported_operations: subgroup.supported_operations,
+                    }
+                };
+
                 PhysicalDevice {
                     raw: pdevice,
                     queue_families,
                     presentation_requested: true,
+                    presentation_queue_family: None,
                     instance: instance.clone(),
                     properties,
                     memory_properties,
+                    subgroup_properties,
                 }
             })
             .collect())
@@ -99,26 +140,36 @@ impl PhysicalDeviceList for Vec<PhysicalDevice> {
             .filter_map(|mut pdevice| {
                 pdevice.presentation_requested = true;
 
-                let supports_presentation =
-                    pdevice
-                        .queue_families
-                        .iter()
-                        .enumerate()
-                        .any(|(queue_index, info)| unsafe {
-                            info.properties
-                                .queue_flags
-                                .contains(vk::QueueFlags::GRAPHICS)
-                                && surface
-                                    .fns
-                                    .get_physical_device_surface_support(
-                                        pdevice.raw,
-                                        queue_index as u32,
-                                        surface.raw,
-                                    )
-                                    .unwrap()
-                        });
-
-                if supports_presentation {
+                let presentation_families: Vec<QueueFamily> = pdevice
+                    .queue_families
+                    .iter()
+                    .filter(|info| unsafe {
+                        surface
+                            .fns
+                            .get_physical_device_surface_support(
+                                pdevice.raw,
+                                info.index,
+                                surface.raw,
+                            )
+                            .unwrap_or(false)
+                    })
+                    .copied()
+                    .collect();
+
+                // Prefer a family which can both render and present, so that we don't
+                // need a separate presentation queue.
+                let presentation_family = presentation_families
+                    .iter()
+                    .find(|info| {
+                        info.properties
+                            .queue_flags
+                            .contains(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+                    })
+                    .or_else(|| presentation_families.first())
+                    .copied();
+
+                if let Some(presentation_family) = presentation_family {
+                    pdevice.presentation_queue_family = Some(presentation_family);
                     Some(pdevice)
                 } else {
                     None
@@ -717,21 +717,22 @@ pub fn create_ray_tracing_pipeline(
         .iter()
         .map(|desc| {
             rspirv_reflect::Reflection::new_from_spirv(&desc.code)
-                .unwrap_or_else(|err| panic!("Failed compiling shader {:?}:\n{:?}", desc.desc, err))
+                .map_err(|err| anyhow::anyhow!("Failed compiling shader {:?}:\n{:?}", desc.desc, err))?
                 .get_descriptor_sets()
-                .unwrap()
+                .map_err(|err| anyhow::anyhow!("Failed to reflect: {:?}", err))
         })
-        .collect::<Vec<_>>();
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
     //log::info!("{:#?}", stage_layouts);
 
-    let (descriptor_set_layouts, set_layout_info) = super::shader::create_descriptor_set_layouts(
-        device,
-        &merge_shader_stage_layouts(stage_layouts),
-        vk::ShaderStageFlags::ALL,
-        //desc.descriptor_set_layout_flags.unwrap_or(&[]),  // TODO: merge flags
-        &desc.descriptor_set_opts,
-    );
+    let (descriptor_set_layouts, set_layout_info, descriptor_pool_sizes) =
+        super::shader::create_descriptor_set_layouts(
+            device,
+            &merge_shader_stage_layouts(stage_layouts),
+            vk::ShaderStageFlags::ALL,
+            //desc.descriptor_set_layout_flags.unwrap_or(&[]),  // TODO: merge flags
+            &desc.descriptor_set_opts,
+        );
 
     unsafe {
         let layout_create_info = vk::PipelineLayoutCreateInfo::builder()
@@ -754,16 +755,16 @@ pub fn create_ray_tracing_pipeline(
         let mut hit_entry_count = 0;
 
         let create_shader_module =
-            |desc: &PipelineShader<Bytes>| -> (ash::vk::ShaderModule, String) {
+            |desc: &PipelineShader<Bytes>| -> anyhow::Result<(ash::vk::ShaderModule, String)> {
                 let shader_info = vk::ShaderModuleCreateInfo::builder()
                     .code(desc.code.as_slice_of::<u32>().unwrap());
 
                 let shader_module = device
                     .raw
                     .create_shader_module(&shader_info, None)
-                    .expect("Shader module error");
+                    .map_err(|err| anyhow::anyhow!("Shader module error: {:?}", err))?;
 
-                (shader_module, desc.desc.entry.clone())
+                Ok((shader_module, desc.desc.entry.clone()))
             };
 
         let mut prev_stage: Option<ShaderPipelineStage> = None;
@@ -776,7 +777,7 @@ pub fn create_ray_tracing_pipeline(
                     assert!(prev_stage.is_none() || prev_stage == Some(ShaderPipelineStage::RayGen));
                     raygen_entry_count += 1;
 
-                    let (module, entry_point) = create_shader_module(desc);
+                    let (module, entry_point) = create_shader_module(desc)?;
 
                     entry_points.push(std::ffi::CString::new(entry_point).unwrap());
                     let entry_point = &**entry_points.last().unwrap();
@@ -805,7 +806,7 @@ pub fn create_ray_tracing_pipeline(
                     );
                     miss_entry_count += 1;
 
-                    let (module, entry_point) = create_shader_module(desc);
+                    let (module, entry_point) = create_shader_module(desc)?;
 
                     entry_points.push(std::ffi::CString::new(entry_point).unwrap());
                     let entry_point = &**entry_points.last().unwrap();
@@ -834,7 +835,7 @@ pub fn create_ray_tracing_pipeline(
                     );
                     hit_entry_count += 1;
 
-                    let (module, entry_point) = create_shader_module(desc);
+                    let (module, entry_point) = create_shader_module(desc)?;
 
                     entry_points.push(std::ffi::CString::new(entry_point).unwrap());
                     let entry_point = &**entry_points.last().unwrap();
@@ -869,7 +870,7 @@ pub fn create_ray_tracing_pipeline(
             .ray_tracing_pipeline_ext
             .create_ray_tracing_pipelines(
                 vk::DeferredOperationKHR::null(),
-                vk::PipelineCache::null(),
+                device.raw_pipeline_cache,
                 &[ash::vk::RayTracingPipelineCreateInfoKHR::builder()
                     .stages(&shader_stages)
                     .groups(&shader_groups)
@@ -878,22 +879,7 @@ pub fn create_ray_tracing_pipeline(
                     .build()],
                 None,
             )
-            .expect("create_ray_tracing_pipelines")[0];
-
-        let mut descriptor_pool_sizes: Vec<vk::DescriptorPoolSize> = Vec::new();
-        for bindings in set_layout_info.iter() {
-            for ty in bindings.values() {
-                if let Some(mut dps) = descriptor_pool_sizes.iter_mut().find(|item| item.ty == *ty)
-                {
-                    dps.descriptor_count += 1;
-                } else {
-                    descriptor_pool_sizes.push(vk::DescriptorPoolSize {
-                        ty: *ty,
-                        descriptor_count: 1,
-                    })
-                }
-            }
-        }
+            .map_err(|err| anyhow::anyhow!("create_ray_tracing_pipelines: {:?}", err))?[0];
 
         let sbt = device
             .create_ray_tracing_shader_table(
@@ -64,6 +64,7 @@ pub fn create_descriptor_set_layouts(
 ) -> (
     Vec<vk::DescriptorSetLayout>,
     Vec<HashMap<u32, vk::DescriptorType>>,
+    Vec<vk::DescriptorPoolSize>,
 ) {
     // dbg!(&descriptor_sets);
 
@@ -95,6 +96,7 @@ pub fn create_descriptor_set_layouts(
     let mut set_layouts: Vec<vk::DescriptorSetLayout> = Vec::with_capacity(set_count as usize);
     let mut set_layout_info: Vec<HashMap<u32, vk::DescriptorType>> =
         Vec::with_capacity(set_count as usize);
+    let mut descriptor_pool_sizes: Vec<vk::DescriptorPoolSize> = Vec::new();
 
     for set_index in 0..set_count {
         let stage_flags = if 0 == set_index {
@@ -143,8 +145,18 @@ pub fn create_descriptor_set_layouts(
                     | rspirv_reflect::DescriptorType::STORAGE_BUFFER_DYNAMIC => bindings.push(
                         vk::DescriptorSetLayoutBinding::builder()
                             .binding(*binding_index)
-                            //.descriptor_count(binding.count)
-                            .descriptor_count(1) // TODO
+                            .descriptor_count(match binding.dimensionality {
+                                rspirv_reflect::DescriptorDimensionality::Single => 1,
+                                rspirv_reflect::DescriptorDimensionality::Array(size) => size,
+                                rspirv_reflect::DescriptorDimensionality::RuntimeArray => {
+                                    // Only sampled images are bindless; bind a single element.
+                                    log::warn!(
+                                        "Runtime-sized arrays are only supported for sampled images; binding one element of {:?}",
+                                        binding
+                                    );
+                                    1
+                                }
+                            })
                             .descriptor_type(match binding.ty {
                                 rspirv_reflect::DescriptorType::UNIFORM_BUFFER => {
                                     vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
@@ -198,7 +210,7 @@ pub fn create_descriptor_set_layouts(
                         bindings.push(
                             vk::DescriptorSetLayoutBinding::builder()
                                 .binding(*binding_index)
-                                .descriptor_count(descriptor_count) // TODO
+                                .descriptor_count(descriptor_count)
                                 .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
                                 .stage_flags(stage_flags)
                                 .build(),
@@ -207,6 +219,15 @@ pub fn create_descriptor_set_layouts(
                     rspirv_reflect::DescriptorType::SAMPLER => {
                         let name_prefix = "sampler_";
                         if let Some(mut spec) = binding.name.strip_prefix(name_prefix) {
+                            // An optional `_aniso<N>` suffix overrides the default anisotropy
+                            let mut max_anisotropy = None;
+                            if let Some((prefix, aniso)) = spec.split_once("_aniso") {
+                                spec = prefix;
+                                max_anisotropy = Some(aniso.parse::<u32>().unwrap_or_else(|_| {
+                                    panic!("Invalid sampler anisotropy: {}", binding.name)
+                                }));
+                            }
+
                             let texel_filter = match &spec[..1] {
                                 "n" => vk::Filter::NEAREST,
                                 "l" => vk::Filter::LINEAR,
@@ -240,6 +261,13 @@ pub fn create_descriptor_set_layouts(
                                             texel_filter,
                                             mipmap_mode,
                                             address_modes,
+                                            max_anisotropy: max_anisotropy.unwrap_or(
+                                                if texel_filter == vk::Filter::LINEAR {
+                                                    16
+                                                } else {
+                                                    0
+                                                },
+                                            ),
                                         }),
                                     )))
                                     .build(),
@@ -279,6 +307,29 @@ pub fn create_descriptor_set_layouts(
                     .unwrap()
             };
 
+            // Bindless bindings are sized for the maximum count, but never get allocated
+            // from the per-dispatch pools, so only reserve a single descriptor for them.
+            for (binding, flags) in bindings.iter().zip(binding_flags.iter()) {
+                let descriptor_count =
+                    if flags.contains(vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT) {
+                        1
+                    } else {
+                        binding.descriptor_count
+                    };
+
+                if let Some(dps) = descriptor_pool_sizes
+                    .iter_mut()
+                    .find(|item| item.ty == binding.descriptor_type)
+                {
+                    dps.descriptor_count += descriptor_count;
+                } else {
+                    descriptor_pool_sizes.push(vk::DescriptorPoolSize {
+                        ty: binding.descriptor_type,
+                        descriptor_count,
+                    })
+                }
+            }
+
             set_layouts.push(set_layout);
             set_layout_info.push(
                 bindings
@@ -302,7 +353,7 @@ pub fn create_descriptor_set_layouts(
         }
     }
 
-    (set_layouts, set_layout_info)
+    (set_layouts, set_layout_info, descriptor_pool_sizes)
 }
 
 #[derive(Builder, Default, Debug, Clone)]
@@ -389,15 +440,16 @@ pub fn create_compute_pipeline(
     spirv: &[u8],
     desc: &ComputePipelineDesc,
 ) -> ComputePipeline {
-    let (descriptor_set_layouts, set_layout_info) = super::shader::create_descriptor_set_layouts(
-        device,
-        &rspirv_reflect::Reflection::new_from_spirv(spirv)
-            .unwrap()
-            .get_descriptor_sets()
-            .unwrap(),
-        vk::ShaderStageFlags::COMPUTE,
-        &desc.descriptor_set_opts,
-    );
+    let (descriptor_set_layouts, set_layout_info, descriptor_pool_sizes) =
+        super::shader::create_descriptor_set_layouts(
+            device,
+            &rspirv_reflect::Reflection::new_from_spirv(spirv)
+                .unwrap()
+                .get_descriptor_sets()
+                .unwrap(),
+            vk::ShaderStageFlags::COMPUTE,
+            &desc.descriptor_set_opts,
+        );
 
     // dbg!(&set_layout_info);
 
@@ -441,25 +493,9 @@ pub fn create_compute_pipeline(
 
         let pipeline = device
             .raw
-            // TODO: pipeline cache
-            .create_compute_pipelines(vk::PipelineCache::null(), &[pipeline_info.build()], None)
+            .create_compute_pipelines(device.raw_pipeline_cache, &[pipeline_info.build()], None)
             .expect("pipeline")[0];
 
-        let mut descriptor_pool_sizes: Vec<vk::DescriptorPoolSize> = Vec::new();
-        for bindings in set_layout_info.iter() {
-            for ty in bindings.values() {
-                if let Some(mut dps) = descriptor_pool_sizes.iter_mut().find(|item| item.ty == *ty)
-                {
-                    dps.descriptor_count += 1;
-                } else {
-                    descriptor_pool_sizes.push(vk::DescriptorPoolSize {
-                        ty: *ty,
-                        descriptor_count: 1,
-                    })
-                }
-            }
-        }
-
         ComputePipeline {
             common: ShaderPipelineCommon {
                 pipeline_layout,
@@ -528,6 +564,13 @@ pub struct RasterPipelineDesc {
     pub depth_write: bool,
     #[builder(default)]
     pub push_constants_bytes: usize,
+    #[builder(default = "vk::PrimitiveTopology::TRIANGLE_LIST")]
+    pub topology: vk::PrimitiveTopology,
+    // Empty unless the vertex shader consumes vertex buffers instead of fetching its own data
+    #[builder(default)]
+    pub vertex_bindings: Vec<vk::VertexInputBindingDescription>,
+    #[builder(default)]
+    pub vertex_attributes: Vec<vk::VertexInputAttributeDescription>,
 }
 
 impl RasterPipelineDesc {
@@ -829,19 +872,20 @@ pub fn create_raster_pipeline(
         .iter()
         .map(|shader| {
             rspirv_reflect::Reflection::new_from_spirv(&shader.code)
-                .unwrap()
+                .map_err(|err| anyhow::anyhow!("Failed to parse SPIR-V: {:?}", err))?
                 .get_descriptor_sets()
-                .unwrap()
+                .map_err(|err| anyhow::anyhow!("Failed to reflect: {:?}", err))
         })
-        .collect::<Vec<_>>();
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
-    let (descriptor_set_layouts, set_layout_info) = super::shader::create_descriptor_set_layouts(
-        device,
-        &merge_shader_stage_layouts(stage_layouts),
-        vk::ShaderStageFlags::ALL_GRAPHICS,
-        //desc.descriptor_set_layout_flags.unwrap_or(&[]),  // TODO: merge flags
-        &desc.descriptor_set_opts,
-    );
+    let (descriptor_set_layouts, set_layout_info, descriptor_pool_sizes) =
+        super::shader::create_descriptor_set_layouts(
+            device,
+            &merge_shader_stage_layouts(stage_layouts),
+            vk::ShaderStageFlags::ALL_GRAPHICS,
+            //desc.descriptor_set_layout_flags.unwrap_or(&[]),  // TODO: merge flags
+            &desc.descriptor_set_opts,
+        );
 
     unsafe {
         let mut layout_create_info =
@@ -873,7 +917,7 @@ pub fn create_raster_pipeline(
                 let shader_module = device
                     .raw
                     .create_shader_module(&shader_info, None)
-                    .expect("Shader module error");
+                    .map_err(|err| anyhow::anyhow!("Shader module error: {:?}", err))?;
 
                 let stage = match desc.desc.stage {
                     ShaderPipelineStage::Vertex => vk::ShaderStageFlags::VERTEX,
@@ -881,23 +925,19 @@ pub fn create_raster_pipeline(
                     _ => unimplemented!(),
                 };
 
-                vk::PipelineShaderStageCreateInfo::builder()
+                Ok(vk::PipelineShaderStageCreateInfo::builder()
                     .module(shader_module)
                     .name(entry_names.add(CString::new(desc.desc.entry.as_str()).unwrap()))
                     .stage(stage)
-                    .build()
+                    .build())
             })
-            .collect();
+            .collect::<anyhow::Result<_>>()?;
 
-        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo {
-            vertex_attribute_description_count: 0,
-            p_vertex_attribute_descriptions: std::ptr::null(),
-            vertex_binding_description_count: 0,
-            p_vertex_binding_descriptions: std::ptr::null(),
-            ..Default::default()
-        };
+        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::builder()
+            .vertex_binding_descriptions(&desc.vertex_bindings)
+            .vertex_attribute_descriptions(&desc.vertex_attributes);
         let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
-            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            topology: desc.topology,
             ..Default::default()
         };
 
@@ -975,26 +1015,12 @@ pub fn create_raster_pipeline(
         let pipeline = device
             .raw
             .create_graphics_pipelines(
-                vk::PipelineCache::null(),
+                device.raw_pipeline_cache,
                 &[graphic_pipeline_info.build()],
                 None,
             )
-            .expect("Unable to create graphics pipeline")[0];
-
-        let mut descriptor_pool_sizes: Vec<vk::DescriptorPoolSize> = Vec::new();
-        for bindings in set_layout_info.iter() {
-            for ty in bindings.values() {
-                if let Some(mut dps) = descriptor_pool_sizes.iter_mut().find(|item| item.ty == *ty)
-                {
-                    dps.descriptor_count += 1;
-                } else {
-                    descriptor_pool_sizes.push(vk::DescriptorPoolSize {
-                        ty: *ty,
-                        descriptor_count: 1,
-                    })
-                }
-            }
-        }
+            .map_err(|(_, err)| anyhow::anyhow!("Unable to create graphics pipeline: {:?}", err))?
+            [0];
 
         Ok(RasterPipeline {
             common: ShaderPipelineCommon {
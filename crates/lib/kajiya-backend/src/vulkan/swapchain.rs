@@ -1,21 +1,54 @@
 use super::{device::Device, surface::Surface};
+use crate::BackendError;
 use anyhow::Result;
 use ash::{extensions::khr, vk};
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 use std::sync::Arc;
 
+/// How the final image gets into the swapchain.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwapchainPresentPath {
+    /// Compute shaders write directly to swapchain images. Requires `STORAGE` usage,
+    /// which many formats (notably sRGB ones) don't support.
+    ComputeWrite,
+
+    /// The final image is rendered to an intermediate, and `vkCmdBlitImage`d to the swapchain.
+    Blit,
+}
+
 #[derive(Clone, Copy, Default)]
 pub struct SwapchainDesc {
     pub format: vk::SurfaceFormatKHR,
     pub dims: vk::Extent2D,
     pub vsync: bool,
+
+    /// Number of images to request; clamped to the limits reported by the surface.
+    pub desired_image_count: u32,
+
+    /// If `None`, or unsupported by the surface, the first supported path from
+    /// `Blit` and `ComputeWrite` is used.
+    pub preferred_present_path: Option<SwapchainPresentPath>,
+}
+
+impl SwapchainDesc {
+    /// Whether the hardware applies the sRGB OETF when the swapchain image is written to.
+    /// If not, shaders writing to the swapchain must encode the output themselves.
+    pub fn is_srgb(&self) -> bool {
+        matches!(
+            self.format.format,
+            vk::Format::B8G8R8A8_SRGB
+                | vk::Format::R8G8B8A8_SRGB
+                | vk::Format::A8B8G8R8_SRGB_PACK32
+        )
+    }
 }
 
 pub struct Swapchain {
     pub(crate) fns: khr::Swapchain,
     pub(crate) raw: vk::SwapchainKHR,
     pub desc: SwapchainDesc,
+    pub present_path: SwapchainPresentPath,
     pub images: Vec<Arc<crate::Image>>,
     pub acquire_semaphores: Vec<vk::Semaphore>,
 
@@ -41,6 +74,9 @@ pub struct SwapchainImage {
 
 pub enum SwapchainAcquireImageErr {
     RecreateFramebuffer,
+    DeviceLost,
+    /// The surface must be recreated, along with the swapchain; see `RenderBackend::recreate_surface`.
+    SurfaceLost,
 }
 
 impl Swapchain {
@@ -56,15 +92,36 @@ impl Swapchain {
     }
 
     pub fn new(device: &Arc<Device>, surface: &Arc<Surface>, desc: SwapchainDesc) -> Result<Self> {
+        Self::create(device, surface, desc, vk::SwapchainKHR::null())
+    }
+
+    /// Creates a swapchain with the same settings and a new extent, e.g. once this one
+    /// is out of date. This one is retired by that, and should be dropped.
+    pub fn recreate(&self, dims: vk::Extent2D) -> Result<Self> {
+        let desc = SwapchainDesc {
+            dims,
+            preferred_present_path: Some(self.present_path),
+            ..self.desc
+        };
+
+        Self::create(&self.device, &self.surface, desc, self.raw)
+    }
+
+    fn create(
+        device: &Arc<Device>,
+        surface: &Arc<Surface>,
+        desc: SwapchainDesc,
+        old_swapchain: vk::SwapchainKHR,
+    ) -> Result<Self> {
         let surface_capabilities = unsafe {
             surface
                 .fns
                 .get_physical_device_surface_capabilities(device.pdevice.raw, surface.raw)
         }?;
 
-        // Triple-buffer so that acquiring an image doesn't stall for >16.6ms at 60Hz on AMD
-        // when frames take >16.6ms to render. Also allows MAILBOX to work.
-        let mut desired_image_count = 3.max(surface_capabilities.min_image_count);
+        let mut desired_image_count = desc
+            .desired_image_count
+            .max(surface_capabilities.min_image_count);
 
         if surface_capabilities.max_image_count != 0 {
             desired_image_count = desired_image_count.min(surface_capabilities.max_image_count);
@@ -82,6 +139,12 @@ impl Swapchain {
             anyhow::bail!("Swapchain resolution cannot be zero");
         }
 
+        // The surface can dictate a different extent than asked for; images are created with that.
+        let desc = SwapchainDesc {
+            dims: surface_resolution,
+            ..desc
+        };
+
         let present_mode_preference = if desc.vsync {
             vec![vk::PresentModeKHR::FIFO_RELAXED, vk::PresentModeKHR::FIFO]
         } else {
@@ -100,6 +163,66 @@ impl Swapchain {
             .unwrap_or(vk::PresentModeKHR::FIFO);
         log::info!("Presentation mode: {:?}", present_mode);
 
+        let format_features = unsafe {
+            device
+                .instance
+                .raw
+                .get_physical_device_format_properties(device.pdevice.raw, desc.format.format)
+        }
+        .optimal_tiling_features;
+
+        let present_path_supported = |path: SwapchainPresentPath| match path {
+            SwapchainPresentPath::ComputeWrite => {
+                surface_capabilities
+                    .supported_usage_flags
+                    .contains(vk::ImageUsageFlags::STORAGE)
+                    && format_features.contains(vk::FormatFeatureFlags::STORAGE_IMAGE)
+            }
+            SwapchainPresentPath::Blit => {
+                surface_capabilities
+                    .supported_usage_flags
+                    .contains(vk::ImageUsageFlags::TRANSFER_DST)
+                    && format_features.contains(vk::FormatFeatureFlags::BLIT_DST)
+            }
+        };
+
+        let present_path = desc
+            .preferred_present_path
+            .into_iter()
+            .chain([
+                SwapchainPresentPath::Blit,
+                SwapchainPresentPath::ComputeWrite,
+            ])
+            .find(|&path| present_path_supported(path))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Swapchain format {:?} supports neither blits nor storage writes",
+                    desc.format.format
+                )
+            })?;
+        match desc.preferred_present_path {
+            Some(preferred) if preferred != present_path => log::warn!(
+                "The {:?} present path is not supported with swapchain format {:?}; falling back to {:?}",
+                preferred,
+                desc.format.format,
+                present_path
+            ),
+            _ => log::info!("Swapchain present path: {:?}", present_path),
+        }
+
+        let mut image_usage = match present_path {
+            SwapchainPresentPath::ComputeWrite => vk::ImageUsageFlags::STORAGE,
+            SwapchainPresentPath::Blit => vk::ImageUsageFlags::TRANSFER_DST,
+        };
+
+        // Allows reading back the presented image, e.g. for screenshots.
+        if surface_capabilities
+            .supported_usage_flags
+            .contains(vk::ImageUsageFlags::TRANSFER_SRC)
+        {
+            image_usage |= vk::ImageUsageFlags::TRANSFER_SRC;
+        }
+
         let pre_transform = if surface_capabilities
             .supported_transforms
             .contains(vk::SurfaceTransformFlagsKHR::IDENTITY)
@@ -109,19 +232,34 @@ impl Swapchain {
             surface_capabilities.current_transform
         };
 
+        // If presenting from a different queue family, share the images between the two
+        // instead of doing queue family ownership transfers.
+        let queue_family_indices = [
+            device.universal_queue.family.index,
+            device.presentation_queue.family.index,
+        ];
+        let (image_sharing_mode, queue_family_indices): (_, &[u32]) =
+            if queue_family_indices[0] != queue_family_indices[1] {
+                (vk::SharingMode::CONCURRENT, &queue_family_indices)
+            } else {
+                (vk::SharingMode::EXCLUSIVE, &[])
+            };
+
         let swapchain_create_info = vk::SwapchainCreateInfoKHR::builder()
             .surface(surface.raw)
             .min_image_count(desired_image_count)
             .image_color_space(desc.format.color_space)
             .image_format(desc.format.format)
             .image_extent(surface_resolution)
-            .image_usage(vk::ImageUsageFlags::STORAGE)
-            .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .image_usage(image_usage)
+            .image_sharing_mode(image_sharing_mode)
+            .queue_family_indices(queue_family_indices)
             .pre_transform(pre_transform)
             .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
             .present_mode(present_mode)
             .clipped(true)
             .image_array_layers(1)
+            .old_swapchain(old_swapchain)
             .build();
 
         let fns = khr::Swapchain::new(&device.instance.raw, &device.raw);
@@ -165,9 +303,9 @@ impl Swapchain {
                     raw: vk_image,
                     desc: crate::ImageDesc {
                         image_type: crate::ImageType::Tex2d,
-                        usage: vk::ImageUsageFlags::STORAGE,
+                        usage: image_usage,
                         flags: vk::ImageCreateFlags::empty(),
-                        format: vk::Format::B8G8R8A8_UNORM,
+                        format: desc.format.format,
                         extent: [desc.dims.width, desc.dims.height, 0],
                         tiling: vk::ImageTiling::OPTIMAL,
                         mip_levels: 1,
@@ -178,7 +316,10 @@ impl Swapchain {
             })
             .collect();
 
-        assert_eq!(desired_image_count, images.len() as u32);
+        // The implementation is allowed to create more images than requested
+        if images.len() as u32 != desired_image_count {
+            log::info!("Swapchain created with {} images", images.len());
+        }
 
         let acquire_semaphores = (0..images.len())
             .map(|_| {
@@ -206,6 +347,7 @@ impl Swapchain {
             fns,
             raw: swapchain,
             desc,
+            present_path,
             images,
             acquire_semaphores,
             rendering_finished_semaphores,
@@ -255,13 +397,20 @@ impl Swapchain {
             {
                 Err(SwapchainAcquireImageErr::RecreateFramebuffer)
             }
+            Err(vk::Result::ERROR_DEVICE_LOST) => {
+                self.device
+                    .report_error(vk::Result::ERROR_DEVICE_LOST.into());
+                Err(SwapchainAcquireImageErr::DeviceLost)
+            }
+            Err(vk::Result::ERROR_SURFACE_LOST_KHR) => Err(SwapchainAcquireImageErr::SurfaceLost),
             err => {
                 panic!("Could not acquire swapchain image: {:?}", err);
             }
         }
     }
 
-    pub fn present_image(&self, image: SwapchainImage) {
+    /// Out-of-date and suboptimal swapchains are not reported here, but on the next acquire.
+    pub fn present_image(&self, image: SwapchainImage) -> Result<(), BackendError> {
         puffin::profile_function!();
 
         let present_info = vk::PresentInfoKHR::builder()
@@ -272,18 +421,17 @@ impl Swapchain {
         unsafe {
             match self
                 .fns
-                .queue_present(self.device.universal_queue.raw, &present_info)
+                .queue_present(self.device.presentation_queue.raw, &present_info)
             {
-                Ok(_) => (),
+                Ok(_) => Ok(()),
                 Err(err)
                     if err == vk::Result::ERROR_OUT_OF_DATE_KHR
                         || err == vk::Result::SUBOPTIMAL_KHR =>
                 {
                     // Handled in the next frame
+                    Ok(())
                 }
-                err => {
-                    panic!("Could not present image: {:?}", err);
-                }
+                Err(err) => Err(self.device.report_error(err.into())),
             }
         }
     }
@@ -25,10 +25,10 @@ use kajiya_backend::{
     vk_sync,
     vulkan::{
         barrier::{
-            get_access_info, image_aspect_mask_from_access_type_and_format, record_image_barrier,
-            ImageBarrier,
+            get_access_info, image_aspect_mask_from_access_type_and_format, record_buffer_barrier,
+            record_image_barrier, BufferBarrier, ImageBarrier,
         },
-        device::{CommandBuffer, Device, VkProfilerData},
+        device::{CommandBuffer, Device, ThreadCommandPools, VkProfilerData},
         image::ImageViewDesc,
         ray_tracing::{RayTracingAcceleration, RayTracingPipelineDesc},
         shader::{ComputePipelineDesc, PipelineShader, PipelineShaderDesc, RasterPipelineDesc},
@@ -37,7 +37,7 @@ use kajiya_backend::{
 };
 use parking_lot::Mutex;
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     ffi::CString,
     hash::Hash,
     marker::PhantomData,
@@ -135,6 +135,8 @@ pub struct RenderGraph {
     passes: Vec<RecordedPass>,
     resources: Vec<GraphResourceInfo>,
     exported_resources: Vec<(ExportableGraphResource, vk_sync::AccessType)>,
+    // Accesses made to exported resources after the graph retires; only contribute usage flags.
+    exported_later_accesses: Vec<(GraphRawResourceHandle, vk_sync::AccessType)>,
     pub(crate) compute_pipelines: Vec<RgComputePipeline>,
     pub(crate) raster_pipelines: Vec<RgRasterPipeline>,
     pub(crate) rt_pipelines: Vec<RgRtPipeline>,
@@ -142,6 +144,9 @@ pub struct RenderGraph {
 
     pub debug_hook: Option<GraphDebugHook>,
     pub debugged_resource: Option<Handle<Image>>,
+
+    pub(crate) frame_scratch: Option<crate::scratch::FrameScratchRegion>,
+    swap_chain_extent: Option<[u32; 2]>,
 }
 
 pub trait ImportExportToRenderGraph
@@ -305,15 +310,23 @@ impl RenderGraph {
             passes: Vec::new(),
             resources: Vec::new(),
             exported_resources: Vec::new(),
+            exported_later_accesses: Vec::new(),
             compute_pipelines: Vec::new(),
             raster_pipelines: Vec::new(),
             rt_pipelines: Vec::new(),
             predefined_descriptor_set_layouts: HashMap::new(),
             debug_hook: None,
             debugged_resource: None,
+            frame_scratch: None,
+            swap_chain_extent: None,
         }
     }
 
+    /// Creates a transient resource. Its usage flags are the union of those in `desc`,
+    /// and those implied by every access to it in the graph, so images which are only
+    /// e.g. blitted from or read back don't need to specify `TRANSFER_SRC` themselves.
+    ///
+    /// Imported resources (including temporal ones) keep the usage they were created with.
     pub fn create<Desc: ResourceDesc>(
         &mut self,
         desc: Desc,
@@ -361,6 +374,37 @@ impl RenderGraph {
         ImportExportToRenderGraph::export(resource, self, access_type)
     }
 
+    /// Like `export`, but also declares how the resource will be used once retrieved from
+    /// the `RetiredRenderGraph`, e.g. `TransferRead` for a readback. Those accesses contribute
+    /// to the usage flags of transient resources, and are checked against imported ones.
+    ///
+    /// The resource is still left in `access_type`; the caller transitions it as needed.
+    pub fn export_for_access<Res: ImportExportToRenderGraph>(
+        &mut self,
+        resource: Handle<Res>,
+        access_type: vk_sync::AccessType,
+        later_accesses: &[vk_sync::AccessType],
+    ) -> ExportedHandle<Res> {
+        let handle = self.export(resource, access_type);
+        self.exported_later_accesses.extend(
+            later_accesses
+                .iter()
+                .map(|access_type| (handle.raw, *access_type)),
+        );
+        handle
+    }
+
+    /// Like `get_swap_chain`, but records the extent the passes writing to it are set up for.
+    /// Should the swapchain have a different extent by the time the frame is drawn, e.g. while
+    /// the window is being resized, the frame is skipped instead of writing out of bounds.
+    pub fn get_swap_chain_with_extent(&mut self, extent: [u32; 2]) -> Handle<Image> {
+        self.swap_chain_extent = Some(extent);
+
+        let mut handle = self.get_swap_chain();
+        handle.desc.extent = [extent[0], extent[1], 1];
+        handle
+    }
+
     pub fn get_swap_chain(&mut self) -> Handle<Image> {
         let res = GraphRawResourceHandle {
             id: self.resources.len() as u32,
@@ -398,6 +442,7 @@ pub struct RenderGraphExecutionParams<'a> {
     pub frame_descriptor_set: vk::DescriptorSet,
     pub frame_constants_layout: FrameConstantsLayout,
     pub profiler_data: &'a VkProfilerData,
+    pub thread_command_pools: &'a ThreadCommandPools,
 }
 
 pub struct RenderGraphPipelines {
@@ -485,7 +530,6 @@ impl RenderGraph {
                         desc: GraphResourceDesc::Image(_),
                         ..
                     })
-                    | GraphResourceInfo::Imported(GraphResourceImportInfo::Image { .. })
                     | GraphResourceInfo::Imported(GraphResourceImportInfo::SwapchainImage) => {
                         let image_usage: vk::ImageUsageFlags =
                             image_access_mask_to_usage_flags(access_mask);
@@ -493,6 +537,26 @@ impl RenderGraph {
                         image_usage_flags[res_access.handle.id as usize] |= image_usage;
                     }
 
+                    // Imported images already exist, so the usage derived here can't be applied
+                    // to them. Catch mismatches here rather than in the validation layers.
+                    GraphResourceInfo::Imported(GraphResourceImportInfo::Image {
+                        resource,
+                        ..
+                    }) => {
+                        let image_usage: vk::ImageUsageFlags =
+                            image_access_mask_to_usage_flags(access_mask);
+
+                        if !resource.desc.usage.contains(image_usage) {
+                            report_missing_image_usage(
+                                resource,
+                                image_usage & !resource.desc.usage,
+                                &pass.name,
+                            );
+                        }
+
+                        image_usage_flags[res_access.handle.id as usize] |= image_usage;
+                    }
+
                     // Buffers
                     GraphResourceInfo::Created(GraphResourceCreateInfo {
                         desc: GraphResourceDesc::Buffer(_),
@@ -540,6 +604,43 @@ impl RenderGraph {
             }
         }
 
+        for (handle, access_type) in &self.exported_later_accesses {
+            let raw_id = handle.id as usize;
+            let access_mask = get_access_info(*access_type).access_mask;
+
+            match &self.resources[raw_id] {
+                GraphResourceInfo::Imported(GraphResourceImportInfo::Image {
+                    resource, ..
+                }) => {
+                    let image_usage = image_access_mask_to_usage_flags(access_mask);
+
+                    if !resource.desc.usage.contains(image_usage) {
+                        report_missing_image_usage(
+                            resource,
+                            image_usage & !resource.desc.usage,
+                            "<export>",
+                        );
+                    }
+
+                    image_usage_flags[raw_id] |= image_usage;
+                }
+                GraphResourceInfo::Created(GraphResourceCreateInfo {
+                    desc: GraphResourceDesc::Image(_),
+                    ..
+                }) => {
+                    image_usage_flags[raw_id] |= image_access_mask_to_usage_flags(access_mask);
+                }
+                GraphResourceInfo::Created(GraphResourceCreateInfo {
+                    desc: GraphResourceDesc::Buffer(_),
+                    ..
+                })
+                | GraphResourceInfo::Imported(GraphResourceImportInfo::Buffer { .. }) => {
+                    buffer_usage_flags[raw_id] |= buffer_access_mask_to_usage_flags(access_mask);
+                }
+                _ => {}
+            }
+        }
+
         ResourceInfo {
             _lifetimes: lifetimes,
             image_usage_flags,
@@ -657,6 +758,25 @@ impl RenderGraph {
     }
 }
 
+fn report_missing_image_usage(image: &Image, missing: vk::ImageUsageFlags, pass_name: &str) {
+    lazy_static::lazy_static! {
+        static ref REPORTED: Mutex<HashSet<(vk::Image, vk::ImageUsageFlags)>> = Default::default();
+    }
+
+    // The graph is compiled every frame; only complain once.
+    if REPORTED.lock().insert((image.raw, missing)) {
+        log::error!(
+            "Pass {:?} needs {:?} usage of an imported {:?} {:?} image, but it was created with {:?}. \
+            Include the usage in the ImageDesc the image is created with.",
+            pass_name,
+            missing,
+            image.desc.format,
+            image.desc.extent,
+            image.desc.usage
+        );
+    }
+}
+
 fn image_access_mask_to_usage_flags(access_mask: vk::AccessFlags) -> vk::ImageUsageFlags {
     match access_mask {
         vk::AccessFlags::SHADER_READ => vk::ImageUsageFlags::SAMPLED,
@@ -705,6 +825,14 @@ fn buffer_access_mask_to_usage_flags(access_mask: vk::AccessFlags) -> vk::Buffer
 }
 
 impl CompiledRenderGraph {
+    pub fn pass_count(&self) -> usize {
+        self.rg.passes.len()
+    }
+
+    pub(crate) fn swap_chain_extent(&self) -> Option<[u32; 2]> {
+        self.rg.swap_chain_extent
+    }
+
     #[must_use]
     pub fn begin_execute<'exec_params, 'constants>(
         self,
@@ -713,6 +841,12 @@ impl CompiledRenderGraph {
         dynamic_constants: &'constants mut DynamicConstants,
     ) -> ExecutingRenderGraph<'exec_params, 'constants> {
         let device = params.device;
+        let trace = trace_execution();
+
+        if trace {
+            log::trace!("rg: executing {} passes", self.rg.passes.len());
+        }
+
         let resources: Vec<RegistryResource> = self
             .rg
             .resources
@@ -723,9 +857,20 @@ impl CompiledRenderGraph {
                     GraphResourceDesc::Image(mut desc) => {
                         desc.usage = self.resource_info.image_usage_flags[resource_idx];
 
-                        let image = transient_resource_cache
-                            .get_image(&desc)
-                            .unwrap_or_else(|| device.create_image(desc, vec![]).unwrap());
+                        let image = match transient_resource_cache.get_image(&desc) {
+                            Some(image) => {
+                                if trace {
+                                    log::trace!("rg: #{} reused image {:?}", resource_idx, desc);
+                                }
+                                image
+                            }
+                            None => {
+                                if trace {
+                                    log::trace!("rg: #{} allocated image {:?}", resource_idx, desc);
+                                }
+                                device.create_image(desc, vec![]).unwrap()
+                            }
+                        };
 
                         RegistryResource {
                             access_type: vk_sync::AccessType::Nothing,
@@ -735,12 +880,24 @@ impl CompiledRenderGraph {
                     GraphResourceDesc::Buffer(mut desc) => {
                         desc.usage = self.resource_info.buffer_usage_flags[resource_idx];
 
-                        let buffer =
-                            transient_resource_cache
-                                .get_buffer(&desc)
-                                .unwrap_or_else(|| {
-                                    device.create_buffer(desc, "rg buffer", None).unwrap()
-                                });
+                        let buffer = match transient_resource_cache.get_buffer(&desc) {
+                            Some(buffer) => {
+                                if trace {
+                                    log::trace!("rg: #{} reused buffer {:?}", resource_idx, desc);
+                                }
+                                buffer
+                            }
+                            None => {
+                                if trace {
+                                    log::trace!(
+                                        "rg: #{} allocated buffer {:?}",
+                                        resource_idx,
+                                        desc
+                                    );
+                                }
+                                device.create_buffer(desc, "rg buffer", None).unwrap()
+                            }
+                        };
 
                         RegistryResource {
                             resource: AnyRenderResource::OwnedBuffer(buffer),
@@ -831,44 +988,100 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
         // While we don't have split barriers yet, this will remove some bubbles
         // which would otherwise occur with temporal resources.
         {
-            let mut resource_first_access_states: HashMap<u32, &mut PassResourceAccessType> =
-                HashMap::with_capacity(self.resources.len());
-
-            for pass in &mut passes[0..first_presentation_pass] {
-                for resource_ref in pass.read.iter_mut().chain(pass.write.iter_mut()) {
-                    resource_first_access_states
-                        .entry(resource_ref.handle.id)
-                        .or_insert(&mut resource_ref.access);
-                }
-            }
+            let initial_transitions = initial_transitions(&mut passes[0..first_presentation_pass]);
 
             let params = &self.resource_registry.execution_params;
-            for (resource_idx, access) in resource_first_access_states {
+            let trace = trace_execution();
+            if trace {
+                log::trace!("rg: initial transitions");
+            }
+
+            for (resource_idx, access) in initial_transitions {
                 let resource = &mut self.resource_registry.resources[resource_idx as usize];
                 Self::transition_resource(
                     params.device,
                     cb,
                     resource,
-                    PassResourceAccessType {
-                        access_type: access.access_type,
-                        sync_type: PassResourceAccessSyncType::SkipSyncIfSameAccessType,
+                    access,
+                    trace,
+                    &if trace {
+                        format!("#{}", resource_idx)
+                    } else {
+                        String::new()
                     },
-                    false,
-                    "",
                 );
-
-                // Skip the sync when this pass is encountered later.
-                access.sync_type = PassResourceAccessSyncType::SkipSyncIfSameAccessType;
             }
         }
 
         for pass in passes.drain(..first_presentation_pass) {
-            Self::record_pass_cb(pass, &mut self.resource_registry, cb);
+            Self::record_pass(pass, &mut self.resource_registry, cb);
         }
 
         self.passes = passes.into();
     }
 
+    // Passes which allow it are recorded into a secondary command buffer from the recording
+    // thread's pool, and executed by `cb`. Recording independent passes on multiple threads
+    // will build on that; until then, they're all recorded on this thread.
+    fn record_pass(
+        pass: RecordedPass,
+        resource_registry: &mut ResourceRegistry,
+        cb: &CommandBuffer,
+    ) {
+        if !pass.can_record_on_secondary_cb() {
+            Self::record_pass_cb(pass, resource_registry, cb);
+            return;
+        }
+
+        let params = &resource_registry.execution_params;
+        let raw_device = &params.device.raw;
+
+        let secondary_cb = CommandBuffer {
+            raw: params
+                .thread_command_pools
+                .allocate_secondary(raw_device)
+                .expect("allocate_secondary"),
+            // Secondary command buffers aren't submitted on their own
+            submit_done_fence: vk::Fence::null(),
+        };
+
+        unsafe {
+            let inheritance_info = vk::CommandBufferInheritanceInfo::default();
+            raw_device
+                .begin_command_buffer(
+                    secondary_cb.raw,
+                    &vk::CommandBufferBeginInfo::builder()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
+                        .inheritance_info(&inheritance_info),
+                )
+                .expect("begin_command_buffer");
+        }
+
+        Self::record_pass_cb(pass, resource_registry, &secondary_cb);
+
+        let raw_device = &resource_registry.execution_params.device.raw;
+        unsafe {
+            raw_device
+                .end_command_buffer(secondary_cb.raw)
+                .expect("end_command_buffer");
+            raw_device.cmd_execute_commands(cb.raw, &[secondary_cb.raw]);
+        }
+    }
+
+    /// Releases the graph's transient resources without recording the presentation passes,
+    /// e.g. when there's no image to present to. As with a retired graph, they can be reused
+    /// by later frames, whose work is submitted after this graph's main command buffer.
+    pub fn release_resources_without_presentation(
+        self,
+        transient_resource_cache: &mut TransientResourceCache,
+    ) {
+        for resource in self.resource_registry.resources {
+            if !matches!(resource.resource, AnyRenderResource::Pending(_)) {
+                release_resource(resource.resource, transient_resource_cache);
+            }
+        }
+    }
+
     #[must_use]
     pub fn record_presentation_cb(
         mut self,
@@ -876,12 +1089,16 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
         swapchain_image: Arc<Image>,
     ) -> RetiredRenderGraph {
         let params = &self.resource_registry.execution_params;
+        let trace = trace_execution();
+        if trace {
+            log::trace!("rg: export transitions");
+        }
 
         // Transition exported images to the requested access types
         for (resource_idx, access_type) in self.exported_resources {
             if access_type != vk_sync::AccessType::Nothing {
-                let resource =
-                    &mut self.resource_registry.resources[resource_idx.raw().id as usize];
+                let resource_idx = resource_idx.raw().id as usize;
+                let resource = &mut self.resource_registry.resources[resource_idx];
                 Self::transition_resource(
                     params.device,
                     cb,
@@ -890,17 +1107,23 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
                         access_type,
                         sync_type: PassResourceAccessSyncType::AlwaysSync,
                     },
-                    false,
-                    "",
+                    trace,
+                    &if trace {
+                        format!("#{}", resource_idx)
+                    } else {
+                        String::new()
+                    },
                 );
             }
         }
 
-        for res in &mut self.resource_registry.resources {
+        let mut swapchain_resource_indices = Vec::new();
+        for (res_idx, res) in self.resource_registry.resources.iter_mut().enumerate() {
             if let AnyRenderResource::Pending(pending) = &mut res.resource {
                 match pending.resource {
                     GraphResourceInfo::Imported(GraphResourceImportInfo::SwapchainImage) => {
                         res.resource = AnyRenderResource::ImportedImage(swapchain_image.clone());
+                        swapchain_resource_indices.push(res_idx);
                     }
                     _ => panic!("Only swapchain can be currently pending"),
                 }
@@ -912,8 +1135,17 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
             Self::record_pass_cb(pass, &mut self.resource_registry, cb);
         }
 
+        // The caller transitions the swapchain image to present from whatever wrote it last,
+        // e.g. a compute pass or a blit. It starts out in `ComputeShaderWrite`.
+        let swapchain_access_type = swapchain_resource_indices
+            .last()
+            .map_or(vk_sync::AccessType::ComputeShaderWrite, |&res_idx| {
+                self.resource_registry.resources[res_idx].access_type
+            });
+
         RetiredRenderGraph {
             resources: self.resource_registry.resources,
+            swapchain_access_type,
         }
     }
 
@@ -923,6 +1155,11 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
         cb: &CommandBuffer,
     ) {
         let params = &resource_registry.execution_params;
+        let trace = trace_execution();
+
+        if trace {
+            log::trace!("rg: pass {:?}", pass.name);
+        }
 
         // Record a crash marker just before this pass
         params
@@ -947,26 +1184,9 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
         {
             let params = &resource_registry.execution_params;
 
-            let mut transitions: Vec<(usize, PassResourceAccessType)> = Vec::new();
-            for resource_ref in pass.read.iter() {
-                transitions.push((
-                    resource_ref.handle.id as usize,
-                    resource_ref.access,
-                    //format!("read {i}"),
-                ));
-            }
-
-            for resource_ref in pass.write.iter() {
-                transitions.push((
-                    resource_ref.handle.id as usize,
-                    resource_ref.access,
-                    //format!("write {i}"),
-                ));
-            }
-
             // TODO: optimize the barriers
 
-            for (resource_idx, access) in transitions {
+            for (resource_idx, access, kind) in pass_transitions(&pass) {
                 let resource = &mut resource_registry.resources[resource_idx];
 
                 Self::transition_resource(
@@ -974,9 +1194,12 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
                     cb,
                     resource,
                     access,
-                    //pass.name == "raster simple",
-                    false,
-                    "",
+                    trace,
+                    &if trace {
+                        format!("{} #{}", kind, resource_idx)
+                    } else {
+                        String::new()
+                    },
                 );
             }
         }
@@ -984,6 +1207,7 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
         let mut api = RenderPassApi {
             cb,
             resources: resource_registry,
+            view_index: pass.view_index,
         };
 
         if let Some(render_fn) = pass.render_fn {
@@ -1018,18 +1242,15 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
         debug: bool,
         dbg_str: &str,
     ) {
-        if unsafe { RG_ALLOW_PASS_OVERLAP }
-            && resource.access_type == access.access_type
-            && matches!(
-                access.sync_type,
-                PassResourceAccessSyncType::SkipSyncIfSameAccessType
-            )
-        {
+        if !needs_barrier(resource.access_type, access) {
+            if debug {
+                log::trace!("\t{dbg_str}: {:?} (no barrier)", access.access_type);
+            }
             return;
         }
 
         if debug {
-            log::info!(
+            log::trace!(
                 "\t{dbg_str}: {:?} -> {:?}",
                 resource.access_type,
                 access.access_type
@@ -1039,7 +1260,7 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
         match resource.resource.borrow() {
             AnyRenderResourceRef::Image(image) => {
                 if debug {
-                    log::info!("\t(image {:?})", image.desc);
+                    log::trace!("\t(image {:?})", image.desc);
                 }
 
                 record_image_barrier(
@@ -1066,24 +1287,19 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
             }
             AnyRenderResourceRef::Buffer(buffer) => {
                 if debug {
-                    log::info!("\t(buffer {:?})", buffer.desc);
+                    log::trace!("\t(buffer {:?})", buffer.desc);
                 }
                 //global_barrier(device, cb, &[resource.access_type], &[access.access_type]);
 
-                vk_sync::cmd::pipeline_barrier(
-                    device.raw.fp_v1_0(),
+                record_buffer_barrier(
+                    device,
                     cb.raw,
-                    None,
-                    &[vk_sync::BufferBarrier {
-                        previous_accesses: &[resource.access_type],
-                        next_accesses: &[access.access_type],
-                        src_queue_family_index: device.universal_queue.family.index,
-                        dst_queue_family_index: device.universal_queue.family.index,
-                        buffer: buffer.raw,
-                        offset: 0,
-                        size: buffer.desc.size,
-                    }],
-                    &[],
+                    BufferBarrier::new(
+                        buffer.raw,
+                        buffer.desc.size,
+                        resource.access_type,
+                        access.access_type,
+                    ),
                 );
 
                 resource.access_type = access.access_type;
@@ -1106,6 +1322,59 @@ impl<'exec_params, 'constants> ExecutingRenderGraph<'exec_params, 'constants> {
     }
 }
 
+/// Finds the first access of every resource used in `passes`. All resources get transitioned
+/// to those at the start of the graph, and the passes are changed to then skip the barrier
+/// if the access type is still the same.
+fn initial_transitions(passes: &mut [RecordedPass]) -> Vec<(u32, PassResourceAccessType)> {
+    let mut resource_first_access_states: HashMap<u32, &mut PassResourceAccessType> =
+        HashMap::new();
+
+    for pass in passes {
+        for resource_ref in pass.read.iter_mut().chain(pass.write.iter_mut()) {
+            resource_first_access_states
+                .entry(resource_ref.handle.id)
+                .or_insert(&mut resource_ref.access);
+        }
+    }
+
+    let mut transitions: Vec<_> = resource_first_access_states
+        .into_iter()
+        .map(|(resource_idx, access)| {
+            // Skip the sync when this pass is encountered later.
+            access.sync_type = PassResourceAccessSyncType::SkipSyncIfSameAccessType;
+            (resource_idx, *access)
+        })
+        .collect();
+    transitions.sort_by_key(|(resource_idx, _)| *resource_idx);
+    transitions
+}
+
+/// Accesses of a pass, in the order its resources get transitioned: reads, then writes.
+fn pass_transitions(pass: &RecordedPass) -> Vec<(usize, PassResourceAccessType, &'static str)> {
+    let reads = pass
+        .read
+        .iter()
+        .map(|resource_ref| (resource_ref.handle.id as usize, resource_ref.access, "read"));
+    let writes = pass.write.iter().map(|resource_ref| {
+        (
+            resource_ref.handle.id as usize,
+            resource_ref.access,
+            "write",
+        )
+    });
+
+    reads.chain(writes).collect()
+}
+
+fn needs_barrier(current: vk_sync::AccessType, access: PassResourceAccessType) -> bool {
+    !(unsafe { RG_ALLOW_PASS_OVERLAP }
+        && current == access.access_type
+        && matches!(
+            access.sync_type,
+            PassResourceAccessSyncType::SkipSyncIfSameAccessType
+        ))
+}
+
 #[allow(dead_code)]
 fn global_barrier(
     device: &Device,
@@ -1127,9 +1396,15 @@ fn global_barrier(
 
 pub struct RetiredRenderGraph {
     resources: Vec<RegistryResource>,
+    swapchain_access_type: vk_sync::AccessType,
 }
 
 impl RetiredRenderGraph {
+    /// Access type the swapchain image was left in by the last pass using it.
+    pub fn swapchain_access_type(&self) -> vk_sync::AccessType {
+        self.swapchain_access_type
+    }
+
     pub fn exported_resource<Res: Resource>(
         &self,
         handle: ExportedHandle<Res>,
@@ -1141,20 +1416,69 @@ impl RetiredRenderGraph {
         )
     }
 
+    /// Retrieves an exported resource which is about to be used with `access_type`,
+    /// and panics if it wasn't created with the usage flags that requires. Declare such
+    /// accesses via `RenderGraph::export_for_access` so transient resources get them.
+    #[track_caller]
+    pub fn exported_resource_for_access<Res: Resource>(
+        &self,
+        handle: ExportedHandle<Res>,
+        access_type: vk_sync::AccessType,
+    ) -> (&Res, vk_sync::AccessType) {
+        let reg_resource = &self.resources[handle.raw.id as usize];
+        let access_mask = get_access_info(access_type).access_mask;
+
+        match reg_resource.resource.borrow() {
+            AnyRenderResourceRef::Image(image) => {
+                let usage = image_access_mask_to_usage_flags(access_mask);
+                assert!(
+                    image.desc.usage.contains(usage),
+                    "Exported {:?} {:?} image lacks {:?} usage needed for {:?}; it has {:?}. \
+                    Declare the access with `RenderGraph::export_for_access`.",
+                    image.desc.format,
+                    image.desc.extent,
+                    usage & !image.desc.usage,
+                    access_type,
+                    image.desc.usage
+                );
+            }
+            AnyRenderResourceRef::Buffer(buffer) => {
+                let usage = buffer_access_mask_to_usage_flags(access_mask);
+                assert!(
+                    buffer.desc.usage.contains(usage),
+                    "Exported buffer of {} bytes lacks {:?} usage needed for {:?}; it has {:?}. \
+                    Declare the access with `RenderGraph::export_for_access`.",
+                    buffer.desc.size,
+                    usage & !buffer.desc.usage,
+                    access_type,
+                    buffer.desc.usage
+                );
+            }
+            AnyRenderResourceRef::RayTracingAcceleration(_) => {}
+        }
+
+        self.exported_resource(handle)
+    }
+
     pub fn release_resources(self, transient_resource_cache: &mut TransientResourceCache) {
         for resource in self.resources {
-            match resource.resource {
-                AnyRenderResource::OwnedImage(image) => {
-                    transient_resource_cache.insert_image(image)
-                }
-                AnyRenderResource::OwnedBuffer(buffer) => {
-                    transient_resource_cache.insert_buffer(buffer)
-                }
-                AnyRenderResource::ImportedImage(_)
-                | AnyRenderResource::ImportedBuffer(_)
-                | AnyRenderResource::ImportedRayTracingAcceleration(_) => {},
-                AnyRenderResource::Pending { .. } => panic!("RetiredRenderGraph::release_resources called while a resource was in Pending state"),
-            }
+            release_resource(resource.resource, transient_resource_cache);
+        }
+    }
+}
+
+fn release_resource(
+    resource: AnyRenderResource,
+    transient_resource_cache: &mut TransientResourceCache,
+) {
+    match resource {
+        AnyRenderResource::OwnedImage(image) => transient_resource_cache.insert_image(image),
+        AnyRenderResource::OwnedBuffer(buffer) => transient_resource_cache.insert_buffer(buffer),
+        AnyRenderResource::ImportedImage(_)
+        | AnyRenderResource::ImportedBuffer(_)
+        | AnyRenderResource::ImportedRayTracingAcceleration(_) => {}
+        AnyRenderResource::Pending { .. } => {
+            panic!("RetiredRenderGraph::release_resources called while a resource was in Pending state")
         }
     }
 }
@@ -1194,6 +1518,9 @@ pub(crate) struct RecordedPass {
     pub render_fn: Option<Box<DynRenderFn>>,
     pub name: String,
     pub idx: usize,
+    pub view_index: u32,
+    pub uses_raster_pipelines: bool,
+    pub uses_compute_or_rt_pipelines: bool,
 }
 
 impl RecordedPass {
@@ -1204,8 +1531,97 @@ impl RecordedPass {
             render_fn: Default::default(),
             name: name.to_owned(),
             idx,
+            view_index: 0,
+            uses_raster_pipelines: false,
+            uses_compute_or_rt_pipelines: false,
         }
     }
+
+    // Secondary command buffers can't begin render passes. Passes with raster pipelines,
+    // or with no pipelines at all (e.g. ones recording raw Vulkan commands), could.
+    fn can_record_on_secondary_cb(&self) -> bool {
+        self.uses_compute_or_rt_pipelines && !self.uses_raster_pipelines
+    }
 }
 
 pub static mut RG_ALLOW_PASS_OVERLAP: bool = true;
+
+/// Log the passes, barriers, and transient resource allocations of every executed graph
+/// at `trace` level. Off by default, as it's very verbose.
+pub static mut RG_TRACE_EXECUTION: bool = false;
+
+fn trace_execution() -> bool {
+    let enabled = unsafe { RG_TRACE_EXECUTION };
+    enabled && log::log_enabled!(log::Level::Trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kajiya_backend::vulkan::image::ImageDesc;
+    use vk_sync::AccessType;
+
+    // An image written by one pass and sampled by a later one, such as a preview thumbnail
+    // drawn by the UI.
+    #[test]
+    fn image_written_then_sampled() {
+        let mut rg = RenderGraph::new();
+        let mut thumbnail = rg.create(ImageDesc::new_2d(vk::Format::R8G8B8A8_UNORM, [64, 64]));
+
+        {
+            let mut pass = rg.add_pass("checkerboard");
+            pass.write(&mut thumbnail, AccessType::ComputeShaderWrite);
+        }
+        {
+            let mut pass = rg.add_pass("sample thumbnail");
+            pass.read(
+                &thumbnail,
+                AccessType::ComputeShaderReadSampledImageOrUniformTexelBuffer,
+            );
+        }
+
+        let usage = rg.calculate_resource_info().image_usage_flags[thumbnail.raw.id as usize];
+        assert!(usage.contains(vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::SAMPLED));
+
+        // Replay the barrier decisions `record_main_cb` makes for the thumbnail,
+        // starting from a newly allocated image.
+        let thumbnail_idx = thumbnail.raw.id;
+        let mut passes = rg.passes;
+
+        let mut accesses: Vec<PassResourceAccessType> = initial_transitions(&mut passes)
+            .into_iter()
+            .filter(|(resource_idx, _)| *resource_idx == thumbnail_idx)
+            .map(|(_, access)| access)
+            .collect();
+        for pass in &passes {
+            accesses.extend(
+                pass_transitions(pass)
+                    .into_iter()
+                    .filter(|(resource_idx, _, _)| *resource_idx == thumbnail_idx as usize)
+                    .map(|(_, access, _)| access),
+            );
+        }
+
+        let mut current = AccessType::Nothing;
+        let mut barriers = Vec::new();
+        for access in accesses {
+            if needs_barrier(current, access) {
+                barriers.push((current, access.access_type));
+            }
+            current = access.access_type;
+        }
+
+        // Transitioned to the write up front, with no barrier in the writing pass itself,
+        // and a read-after-write barrier before sampling.
+        assert_eq!(
+            barriers,
+            vec![
+                (AccessType::Nothing, AccessType::ComputeShaderWrite),
+                (
+                    AccessType::ComputeShaderWrite,
+                    AccessType::ComputeShaderReadSampledImageOrUniformTexelBuffer
+                ),
+            ]
+        );
+    }
+}
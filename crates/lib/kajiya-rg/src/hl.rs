@@ -268,7 +268,12 @@ impl<'rg, RgPipelineHandle> SimpleRenderPass<'rg, RgPipelineHandle> {
         self
     }
 
-    pub fn read_array(mut self, handles: &[Handle<Image>]) -> Self {
+    /// Binds `handles` as an array of descriptors in a single binding, e.g. `Texture2D tex[8]`.
+    pub fn read_array<Res>(mut self, handles: &[Handle<Res>]) -> Self
+    where
+        Res: Resource + 'static,
+        Vec<Ref<Res, GpuSrv>>: BindRgRef,
+    {
         assert!(!handles.is_empty());
 
         let handle_refs = handles
@@ -326,6 +331,24 @@ impl<'rg, RgPipelineHandle> SimpleRenderPass<'rg, RgPipelineHandle> {
         self
     }
 
+    /// Binds `handles` as an array of descriptors in a single binding, e.g. `RWTexture2D tex[8]`.
+    pub fn write_array<Res>(mut self, handles: &mut [Handle<Res>]) -> Self
+    where
+        Res: Resource + 'static,
+        Vec<Ref<Res, GpuUav>>: BindRgRef,
+    {
+        assert!(!handles.is_empty());
+
+        let handle_refs = handles
+            .iter_mut()
+            .map(|handle| self.pass.write(handle, AccessType::AnyShaderWrite))
+            .collect::<Vec<_>>();
+
+        self.state.bindings.push(BindRgRef::bind(&handle_refs));
+
+        self
+    }
+
     pub fn write_no_sync<Res>(mut self, handle: &mut Handle<Res>) -> Self
     where
         Res: Resource + 'static,
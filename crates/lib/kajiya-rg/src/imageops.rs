@@ -33,6 +33,10 @@ pub fn clear_depth(rg: &mut RenderGraph, img: &mut rg::Handle<Image>) {
     });
 }
 
+/// Fills `img` with `clear_color`, specified in linear space. As with any write, images
+/// in `_SRGB` formats get the value sRGB-encoded by the hardware; for HDR targets
+/// (float formats), the value is radiance, and goes through exposure and tone mapping
+/// like everything else rendered into them.
 pub fn clear_color(rg: &mut RenderGraph, img: &mut rg::Handle<Image>, clear_color: [f32; 4]) {
     let mut pass = rg.add_pass("clear color");
     let output_ref = pass.write(img, AccessType::TransferWrite);
@@ -63,3 +67,55 @@ pub fn clear_color(rg: &mut RenderGraph, img: &mut rg::Handle<Image>, clear_colo
         Ok(())
     });
 }
+
+/// Blits the first mip of `src` over the whole of `dst`, with linear filtering if their sizes differ.
+pub fn blit_image(rg: &mut RenderGraph, src: &rg::Handle<Image>, dst: &mut rg::Handle<Image>) {
+    let mut pass = rg.add_pass("blit image");
+    let src_ref = pass.read(src, AccessType::TransferRead);
+    let dst_ref = pass.write(dst, AccessType::TransferWrite);
+
+    pass.render(move |api| {
+        let raw_device = &api.device().raw;
+        let cb = api.cb;
+
+        let src = api.resources.image(src_ref);
+        let dst = api.resources.image(dst_ref);
+
+        let subresource = vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: 0,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let far_corner = |extent: [u32; 3]| vk::Offset3D {
+            x: extent[0] as i32,
+            y: extent[1] as i32,
+            z: 1,
+        };
+
+        let filter = if src.desc.extent[..2] == dst.desc.extent[..2] {
+            vk::Filter::NEAREST
+        } else {
+            vk::Filter::LINEAR
+        };
+
+        unsafe {
+            raw_device.cmd_blit_image(
+                cb.raw,
+                src.raw,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.raw,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[vk::ImageBlit {
+                    src_subresource: subresource,
+                    src_offsets: [vk::Offset3D::default(), far_corner(src.desc.extent)],
+                    dst_subresource: subresource,
+                    dst_offsets: [vk::Offset3D::default(), far_corner(dst.desc.extent)],
+                }],
+                filter,
+            );
+        }
+
+        Ok(())
+    });
+}
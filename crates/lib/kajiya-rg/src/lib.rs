@@ -2,8 +2,11 @@ mod graph;
 mod hl;
 mod pass_api;
 mod pass_builder;
+mod renderdoc;
 mod resource;
 mod resource_registry;
+mod scratch;
+mod screenshot;
 mod temporal;
 
 pub mod imageops;
@@ -15,4 +18,6 @@ pub use pass_api::*;
 pub use pass_builder::*;
 pub use resource::*;
 pub use resource_registry::ResourceRegistry;
+pub use scratch::{FrameScratchAllocator, ScratchBuffer, FRAME_SCRATCH_SIZE_BYTES};
+pub use screenshot::CapturedImage;
 pub use temporal::*;
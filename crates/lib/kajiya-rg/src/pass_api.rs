@@ -15,6 +15,7 @@ use kajiya_backend::{
         MAX_DYNAMIC_CONSTANTS_STORAGE_BUFFER_BYTES,
     },
     vulkan::{
+        descriptor_set_cache::{DescriptorSetCacheBinding, DescriptorSetCacheKey},
         device::{CommandBuffer, Device},
         image::*,
         ray_tracing::{RayTracingAcceleration, RayTracingPipeline},
@@ -29,12 +30,14 @@ use kajiya_backend::{
 pub struct RenderPassApi<'a, 'exec_params, 'constants> {
     pub cb: &'a CommandBuffer,
     pub resources: &'a mut ResourceRegistry<'exec_params, 'constants>,
+    pub(crate) view_index: u32,
 }
 
 pub enum DescriptorSetBinding {
     Image(vk::DescriptorImageInfo),
     ImageArray(Vec<vk::DescriptorImageInfo>),
     Buffer(vk::DescriptorBufferInfo),
+    BufferArray(Vec<vk::DescriptorBufferInfo>),
     RayTracingAcceleration(vk::AccelerationStructureKHR),
     DynamicBuffer {
         buffer: vk::DescriptorBufferInfo,
@@ -115,6 +118,14 @@ impl<'a, 'exec_params, 'constants> RenderPassApi<'a, 'exec_params, 'constants> {
         let device = self.resources.execution_params.device;
         let pipeline_arc = self.resources.compute_pipeline(binding.pipeline);
 
+        device.record_crash_marker(
+            self.cb,
+            format!(
+                "bind compute pipeline {}",
+                self.resources.compute_pipeline_name(binding.pipeline)
+            ),
+        );
+
         self.bind_pipeline_common(device, pipeline_arc.as_ref(), &binding.binding)?;
 
         Ok(BoundComputePipeline {
@@ -130,6 +141,9 @@ impl<'a, 'exec_params, 'constants> RenderPassApi<'a, 'exec_params, 'constants> {
         let device = self.resources.execution_params.device;
         let pipeline_arc = self.resources.raster_pipeline(binding.pipeline);
 
+        // No crash marker here: raster pipelines are bound inside a render pass,
+        // where the buffer fill used for markers is not allowed.
+
         self.bind_pipeline_common(device, pipeline_arc.as_ref(), &binding.binding)?;
 
         Ok(BoundRasterPipeline {
@@ -145,6 +159,14 @@ impl<'a, 'exec_params, 'constants> RenderPassApi<'a, 'exec_params, 'constants> {
         let device = self.resources.execution_params.device;
         let pipeline_arc = self.resources.ray_tracing_pipeline(binding.pipeline);
 
+        device.record_crash_marker(
+            self.cb,
+            format!(
+                "bind ray tracing pipeline {}",
+                self.resources.ray_tracing_pipeline_name(binding.pipeline)
+            ),
+        );
+
         self.bind_pipeline_common(device, pipeline_arc.as_ref(), &binding.binding)?;
 
         Ok(BoundRayTracingPipeline {
@@ -168,6 +190,7 @@ impl<'a, 'exec_params, 'constants> RenderPassApi<'a, 'exec_params, 'constants> {
         }
 
         // Bind frame constants
+        let frame_constants_layout = &self.resources.execution_params.frame_constants_layout;
         if pipeline
             .set_layout_info
             .get(2)
@@ -182,18 +205,9 @@ impl<'a, 'exec_params, 'constants> RenderPassApi<'a, 'exec_params, 'constants> {
                     2,
                     &[self.resources.execution_params.frame_descriptor_set],
                     &[
-                        self.resources
-                            .execution_params
-                            .frame_constants_layout
-                            .globals_offset,
-                        self.resources
-                            .execution_params
-                            .frame_constants_layout
-                            .instance_dynamic_parameters_offset,
-                        self.resources
-                            .execution_params
-                            .frame_constants_layout
-                            .triangle_lights_offset,
+                        frame_constants_layout.globals_offset_for_view(self.view_index),
+                        frame_constants_layout.instance_dynamic_parameters_offset,
+                        frame_constants_layout.triangle_lights_offset,
                     ],
                 );
             }
@@ -238,9 +252,28 @@ impl<'a, 'exec_params, 'constants> RenderPassApi<'a, 'exec_params, 'constants> {
                                         .buffer_from_raw_handle::<GpuSrv>(buffer.handle)
                                         .raw,
                                 )
-                                .range(vk::WHOLE_SIZE)
+                                .offset(buffer.offset)
+                                .range(buffer.range)
                                 .build(),
                         ),
+                        RenderPassBinding::BufferArray(buffers) => {
+                            DescriptorSetBinding::BufferArray(
+                                buffers
+                                    .iter()
+                                    .map(|buffer| {
+                                        vk::DescriptorBufferInfo::builder()
+                                            .buffer(
+                                                self.resources
+                                                    .buffer_from_raw_handle::<GpuSrv>(buffer.handle)
+                                                    .raw,
+                                            )
+                                            .offset(buffer.offset)
+                                            .range(buffer.range)
+                                            .build()
+                                    })
+                                    .collect(),
+                            )
+                        }
                         RenderPassBinding::RayTracingAcceleration(acc) => {
                             DescriptorSetBinding::RayTracingAcceleration(
                                 self.resources
@@ -485,6 +518,39 @@ impl<'api, 'a, 'exec_params, 'constants> BoundRasterPipeline<'api, 'a, 'exec_par
                 )
         }
     }
+
+    /// Binds `(buffer, offset)` pairs to consecutive vertex input bindings starting at `first_binding`.
+    /// The buffers must have been read by the pass with `AccessType::VertexBuffer`.
+    pub fn bind_vertex_buffers(&self, first_binding: u32, buffers: &[(Ref<Buffer, GpuSrv>, u64)]) {
+        let raw_buffers: Vec<vk::Buffer> = buffers
+            .iter()
+            .map(|(buffer, _)| self.api.resources.buffer(*buffer).raw)
+            .collect();
+        let offsets: Vec<vk::DeviceSize> = buffers.iter().map(|(_, offset)| *offset).collect();
+
+        unsafe {
+            self.api.device().raw.cmd_bind_vertex_buffers(
+                self.api.cb.raw,
+                first_binding,
+                &raw_buffers,
+                &offsets,
+            );
+        }
+    }
+
+    /// Non-indexed draw. Unless the pipeline declares vertex inputs,
+    /// shaders fetch vertex data themselves, e.g. from a dynamic storage buffer.
+    pub fn draw(&self, vertex_count: u32, instance_count: u32) {
+        unsafe {
+            self.api.resources.execution_params.device.raw.cmd_draw(
+                self.api.cb.raw,
+                vertex_count,
+                instance_count,
+                0,
+                0,
+            );
+        }
+    }
 }
 
 pub struct RenderPassImageBinding {
@@ -495,6 +561,8 @@ pub struct RenderPassImageBinding {
 
 pub struct RenderPassBufferBinding {
     handle: GraphRawResourceHandle,
+    offset: vk::DeviceSize,
+    range: vk::DeviceSize,
 }
 
 pub struct RenderPassRayTracingAccelerationBinding {
@@ -505,6 +573,7 @@ pub enum RenderPassBinding {
     Image(RenderPassImageBinding),
     ImageArray(Vec<RenderPassImageBinding>),
     Buffer(RenderPassBufferBinding),
+    BufferArray(Vec<RenderPassBufferBinding>),
     RayTracingAcceleration(RenderPassRayTracingAccelerationBinding),
     DynamicConstants(u32),
     DynamicConstantsStorageBuffer(u32),
@@ -600,6 +669,23 @@ impl BindRgRef for Ref<Image, GpuUav> {
     }
 }
 
+impl BindRgRef for Vec<Ref<Image, GpuUav>> {
+    fn bind(&self) -> RenderPassBinding {
+        let view_desc = ImageViewDesc::default();
+
+        RenderPassBinding::ImageArray(
+            self.iter()
+                .copied()
+                .map(|handle| RenderPassImageBinding {
+                    handle: handle.handle,
+                    view_desc,
+                    image_layout: vk::ImageLayout::GENERAL,
+                })
+                .collect(),
+        )
+    }
+}
+
 impl Ref<Image, GpuUav> {
     pub fn bind_view(&self, view_desc: ImageViewDescBuilder) -> RenderPassBinding {
         RenderPassBinding::Image(RenderPassImageBinding {
@@ -612,16 +698,62 @@ impl Ref<Image, GpuUav> {
 
 impl BindRgRef for Ref<Buffer, GpuSrv> {
     fn bind(&self) -> RenderPassBinding {
+        self.bind_range(0, vk::WHOLE_SIZE)
+    }
+}
+
+impl Ref<Buffer, GpuSrv> {
+    /// Binds `range` bytes starting at `offset`, e.g. for a `ScratchBuffer`.
+    pub fn bind_range(&self, offset: vk::DeviceSize, range: vk::DeviceSize) -> RenderPassBinding {
         RenderPassBinding::Buffer(RenderPassBufferBinding {
             handle: self.handle,
+            offset,
+            range,
         })
     }
 }
 
+impl BindRgRef for Vec<Ref<Buffer, GpuSrv>> {
+    fn bind(&self) -> RenderPassBinding {
+        RenderPassBinding::BufferArray(
+            self.iter()
+                .map(|buffer| RenderPassBufferBinding {
+                    handle: buffer.handle,
+                    offset: 0,
+                    range: vk::WHOLE_SIZE,
+                })
+                .collect(),
+        )
+    }
+}
+
 impl BindRgRef for Ref<Buffer, GpuUav> {
     fn bind(&self) -> RenderPassBinding {
+        self.bind_range(0, vk::WHOLE_SIZE)
+    }
+}
+
+impl BindRgRef for Vec<Ref<Buffer, GpuUav>> {
+    fn bind(&self) -> RenderPassBinding {
+        RenderPassBinding::BufferArray(
+            self.iter()
+                .map(|buffer| RenderPassBufferBinding {
+                    handle: buffer.handle,
+                    offset: 0,
+                    range: vk::WHOLE_SIZE,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Ref<Buffer, GpuUav> {
+    /// Binds `range` bytes starting at `offset`, e.g. for a `ScratchBuffer`.
+    pub fn bind_range(&self, offset: vk::DeviceSize, range: vk::DeviceSize) -> RenderPassBinding {
         RenderPassBinding::Buffer(RenderPassBufferBinding {
             handle: self.handle,
+            offset,
+            range,
         })
     }
 }
@@ -651,6 +783,48 @@ fn bind_descriptor_set(
         return;
     };
 
+    let bindings = bindings
+        .iter()
+        .enumerate()
+        .filter(|(binding_idx, _)| shader_set_info.contains_key(&(*binding_idx as u32)));
+
+    let dynamic_offsets: Vec<u32> = bindings
+        .clone()
+        .filter_map(|(_, binding)| match binding {
+            DescriptorSetBinding::DynamicBuffer { offset, .. }
+            | DescriptorSetBinding::DynamicStorageBuffer { offset, .. } => Some(*offset),
+            _ => None,
+        })
+        .collect();
+
+    let set_layout = pipeline.descriptor_set_layouts[set_index as usize];
+    let cache_key = device
+        .descriptor_set_caching_enabled()
+        .then(|| DescriptorSetCacheKey {
+            layout: set_layout,
+            bindings: bindings
+                .clone()
+                .map(|(_, binding)| descriptor_set_cache_binding(binding))
+                .collect(),
+        });
+
+    if let Some(descriptor_set) = cache_key
+        .as_ref()
+        .and_then(|key| device.cached_descriptor_set(key))
+    {
+        unsafe {
+            device.raw.cmd_bind_descriptor_sets(
+                cb.raw,
+                pipeline.pipeline_bind_point,
+                pipeline.pipeline_layout,
+                set_index,
+                &[descriptor_set],
+                dynamic_offsets.as_slice(),
+            );
+        }
+        return;
+    }
+
     let image_info = TempList::new();
     let buffer_info = TempList::new();
     let accel_info: TempList<UnsafeCell<vk::WriteDescriptorSetAccelerationStructureKHR>> =
@@ -665,25 +839,18 @@ fn bind_descriptor_set(
 
         unsafe { raw_device.create_descriptor_pool(&descriptor_pool_create_info, None) }.unwrap()
     };
-    device.defer_release(descriptor_pool);
 
     let descriptor_set = {
         let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo::builder()
             .descriptor_pool(descriptor_pool)
-            .set_layouts(std::slice::from_ref(
-                &pipeline.descriptor_set_layouts[set_index as usize],
-            ));
+            .set_layouts(std::slice::from_ref(&set_layout));
 
         unsafe { raw_device.allocate_descriptor_sets(&descriptor_set_allocate_info) }.unwrap()[0]
     };
 
     unsafe {
-        let mut dynamic_offsets: Vec<u32> = Vec::new();
         let descriptor_writes: Vec<vk::WriteDescriptorSet> =
             bindings
-                .iter()
-                .enumerate()
-                .filter(|(binding_idx, _)| shader_set_info.contains_key(&(*binding_idx as u32)))
                 .map(|(binding_idx, binding)| {
                     let write = vk::WriteDescriptorSet::builder()
                         .dst_set(descriptor_set)
@@ -719,20 +886,22 @@ fn bind_descriptor_set(
                             .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
                             .buffer_info(std::slice::from_ref(buffer_info.add(*buffer)))
                             .build(),
-                        DescriptorSetBinding::DynamicBuffer { buffer, offset } => {
-                            dynamic_offsets.push(*offset);
-                            write
-                                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
-                                .buffer_info(std::slice::from_ref(buffer_info.add(*buffer)))
-                                .build()
-                        }
-                        DescriptorSetBinding::DynamicStorageBuffer { buffer, offset } => {
-                            dynamic_offsets.push(*offset);
+                        DescriptorSetBinding::BufferArray(buffers) => {
+                            assert!(!buffers.is_empty());
+
                             write
-                                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER_DYNAMIC)
-                                .buffer_info(std::slice::from_ref(buffer_info.add(*buffer)))
+                                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                                .buffer_info(buffers.as_slice())
                                 .build()
                         }
+                        DescriptorSetBinding::DynamicBuffer { buffer, .. } => write
+                            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+                            .buffer_info(std::slice::from_ref(buffer_info.add(*buffer)))
+                            .build(),
+                        DescriptorSetBinding::DynamicStorageBuffer { buffer, .. } => write
+                            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER_DYNAMIC)
+                            .buffer_info(std::slice::from_ref(buffer_info.add(*buffer)))
+                            .build(),
                         DescriptorSetBinding::RayTracingAcceleration(acc) => {
                             let mut write = write
                             .descriptor_type(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR)
@@ -764,4 +933,43 @@ fn bind_descriptor_set(
             dynamic_offsets.as_slice(),
         );
     }
+
+    if let Some(key) = cache_key {
+        device.cache_descriptor_set(key, descriptor_pool, descriptor_set);
+    } else {
+        device.defer_release(descriptor_pool);
+    }
+}
+
+fn descriptor_set_cache_binding(binding: &DescriptorSetBinding) -> DescriptorSetCacheBinding {
+    match binding {
+        DescriptorSetBinding::Image(image) => DescriptorSetCacheBinding::Image {
+            view: image.image_view,
+            layout: image.image_layout,
+        },
+        DescriptorSetBinding::ImageArray(images) => DescriptorSetCacheBinding::ImageArray(
+            images
+                .iter()
+                .map(|image| (image.image_view, image.image_layout))
+                .collect(),
+        ),
+        DescriptorSetBinding::Buffer(buffer)
+        | DescriptorSetBinding::DynamicBuffer { buffer, .. }
+        | DescriptorSetBinding::DynamicStorageBuffer { buffer, .. } => {
+            DescriptorSetCacheBinding::Buffer {
+                buffer: buffer.buffer,
+                offset: buffer.offset,
+                range: buffer.range,
+            }
+        }
+        DescriptorSetBinding::BufferArray(buffers) => DescriptorSetCacheBinding::BufferArray(
+            buffers
+                .iter()
+                .map(|buffer| (buffer.buffer, buffer.offset, buffer.range))
+                .collect(),
+        ),
+        DescriptorSetBinding::RayTracingAcceleration(acc) => {
+            DescriptorSetCacheBinding::RayTracingAcceleration(*acc)
+        }
+    }
 }
@@ -146,6 +146,9 @@ impl<'rg> PassBuilder<'rg> {
         self.write_impl(handle, access_type, PassResourceAccessSyncType::AlwaysSync)
     }
 
+    /// Reads a resource, e.g. sampling an image rendered by an earlier pass in the graph.
+    /// The barrier from the previous access, including any image layout transition,
+    /// is inserted before this pass executes.
     pub fn read<Res: Resource>(
         &mut self,
         handle: &Handle<Res>,
@@ -267,6 +270,7 @@ impl<'rg> PassBuilder<'rg> {
         }
 
         self.rg.compute_pipelines.push(RgComputePipeline { desc });
+        self.pass.as_mut().unwrap().uses_compute_or_rt_pipelines = true;
 
         RgComputePipelineHandle { id }
     }
@@ -293,6 +297,7 @@ impl<'rg> PassBuilder<'rg> {
             shaders: shaders.to_vec(),
             desc,
         });
+        self.pass.as_mut().unwrap().uses_raster_pipelines = true;
 
         RgRasterPipelineHandle { id }
     }
@@ -318,10 +323,21 @@ impl<'rg> PassBuilder<'rg> {
             shaders: shaders.to_vec(),
             desc,
         });
+        self.pass.as_mut().unwrap().uses_compute_or_rt_pipelines = true;
 
         RgRtPipelineHandle { id }
     }
 
+    /// Renders this pass from an additional view, whose frame constants are at
+    /// `FrameConstantsLayout::view_globals_offsets[view_index - 1]`. View 0, the default,
+    /// is the main one.
+    ///
+    /// All views share the graph, so a resource written by one view's passes and read
+    /// by another's (e.g. a shadow map) gets the same barriers as any other dependency.
+    pub fn set_view(&mut self, view_index: u32) {
+        self.pass.as_mut().unwrap().view_index = view_index;
+    }
+
     pub fn render(
         mut self,
         render: impl (FnOnce(&mut RenderPassApi) -> Result<(), BackendError>) + 'static,
@@ -0,0 +1,86 @@
+//! Minimal bindings to the RenderDoc in-application API, for triggering captures
+//! from code. Only used when RenderDoc has already injected itself into the process;
+//! the library is never loaded on demand.
+
+use std::{ffi::c_void, ptr};
+
+// eRENDERDOC_API_Version_1_1_0
+const RENDERDOC_API_VERSION_1_1_0: u32 = 10100;
+
+type GetApiFn = unsafe extern "C" fn(version: u32, out_api: *mut *mut c_void) -> i32;
+type StartFrameCaptureFn = unsafe extern "C" fn(device: *mut c_void, window: *mut c_void);
+type IsFrameCapturingFn = unsafe extern "C" fn() -> u32;
+type EndFrameCaptureFn = unsafe extern "C" fn(device: *mut c_void, window: *mut c_void) -> u32;
+
+/// Layout of `RENDERDOC_API_1_1_0` up to the functions used here.
+#[repr(C)]
+struct RenderDocApi {
+    // GetAPIVersion through SetActiveWindow
+    _unused: [*const c_void; 19],
+    start_frame_capture: StartFrameCaptureFn,
+    _is_frame_capturing: IsFrameCapturingFn,
+    end_frame_capture: EndFrameCaptureFn,
+}
+
+pub(crate) struct RenderDoc {
+    // Keeps the module referenced for as long as the function pointers are in use.
+    _lib: libloading::Library,
+    start_frame_capture: StartFrameCaptureFn,
+    end_frame_capture: EndFrameCaptureFn,
+}
+
+impl RenderDoc {
+    /// Returns `None` if RenderDoc isn't attached to the process.
+    pub(crate) fn load() -> Option<Self> {
+        let lib = open_if_loaded()?;
+
+        unsafe {
+            let get_api = lib.get::<GetApiFn>(b"RENDERDOC_GetAPI\0").ok()?;
+
+            let mut api: *mut c_void = ptr::null_mut();
+            if get_api(RENDERDOC_API_VERSION_1_1_0, &mut api) != 1 || api.is_null() {
+                return None;
+            }
+
+            let api = &*(api as *const RenderDocApi);
+            let start_frame_capture = api.start_frame_capture;
+            let end_frame_capture = api.end_frame_capture;
+
+            Some(Self {
+                _lib: lib,
+                start_frame_capture,
+                end_frame_capture,
+            })
+        }
+    }
+
+    /// Captures all devices and windows until `end_frame_capture`.
+    pub(crate) fn start_frame_capture(&self) {
+        unsafe { (self.start_frame_capture)(ptr::null_mut(), ptr::null_mut()) }
+    }
+
+    /// Returns `false` if the capture failed.
+    pub(crate) fn end_frame_capture(&self) -> bool {
+        unsafe { (self.end_frame_capture)(ptr::null_mut(), ptr::null_mut()) == 1 }
+    }
+}
+
+#[cfg(unix)]
+fn open_if_loaded() -> Option<libloading::Library> {
+    unsafe {
+        libloading::os::unix::Library::open(
+            Some("librenderdoc.so"),
+            libc::RTLD_NOW | libc::RTLD_NOLOAD,
+        )
+    }
+    .ok()
+    .map(Into::into)
+}
+
+#[cfg(windows)]
+fn open_if_loaded() -> Option<libloading::Library> {
+    #[allow(unused_unsafe)]
+    unsafe { libloading::os::windows::Library::open_already_loaded("renderdoc.dll") }
+        .ok()
+        .map(Into::into)
+}
@@ -1,7 +1,8 @@
 use crate::{
-    CompiledRenderGraph, ExecutingRenderGraph, ExportedTemporalRenderGraphState,
-    PredefinedDescriptorSet, RenderGraphExecutionParams, TemporalRenderGraph,
-    TemporalRenderGraphState, TemporalResourceState,
+    renderdoc::RenderDoc, screenshot, CapturedImage, CompiledRenderGraph, ExecutingRenderGraph,
+    ExportedTemporalRenderGraphState, FrameScratchAllocator, PredefinedDescriptorSet,
+    RenderGraphExecutionParams, TemporalRenderGraph, TemporalRenderGraphState,
+    TemporalResourceState, FRAME_SCRATCH_SIZE_BYTES,
 };
 use kajiya_backend::{
     ash::vk,
@@ -10,12 +11,22 @@ use kajiya_backend::{
     rspirv_reflect,
     transient_resource_cache::TransientResourceCache,
     vk_sync,
-    vulkan::{self, swapchain::Swapchain, RenderBackend},
-    Device,
+    vulkan::{
+        self,
+        image::Image,
+        swapchain::{Swapchain, SwapchainAcquireImageErr},
+        RenderBackend,
+    },
+    BackendError, Device,
 };
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use turbosloth::*;
 use vulkan::buffer::{Buffer, BufferDesc};
 
@@ -40,6 +51,35 @@ pub struct Renderer {
 
     compiled_rg: Option<CompiledRenderGraph>,
     temporal_rg_state: TemporalRg,
+
+    last_frame_stats: Option<FrameStats>,
+    pending_screenshot: Option<PathBuf>,
+    pending_frame_capture: bool,
+    captured_frame: Option<anyhow::Result<CapturedImage>>,
+
+    renderdoc: Option<RenderDoc>,
+    pending_capture_frames: u32,
+
+    frame_scratch: FrameScratchAllocator,
+
+    low_latency: bool,
+    output_dims: Option<[u32; 2]>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FrameStats {
+    /// CPU time spent recording and submitting the frame, excluding `swapchain_acquire_time`.
+    pub cpu_record_time: Duration,
+
+    /// Sum of GPU pass timings from the most recent profiler report. This lags
+    /// behind the CPU by a few frames, as timestamps are read back asynchronously.
+    pub gpu_frame_time: Option<Duration>,
+
+    /// Time spent blocked on acquiring the swapchain image.
+    pub swapchain_acquire_time: Duration,
+
+    pub pass_count: usize,
+    pub dynamic_constants_bytes: usize,
 }
 
 lazy_static::lazy_static! {
@@ -77,26 +117,166 @@ lazy_static::lazy_static! {
     .collect();
 }
 
+/// A caller-owned image to render into instead of the swapchain, for interop
+/// with applications which own presentation themselves.
+///
+/// The image must have been created with `STORAGE` usage, as the presentation
+/// passes of the render graph write to it from compute shaders.
+pub struct ExternalImageTarget {
+    pub image: Arc<Image>,
+
+    /// Access type the image is in when handed over to the renderer.
+    pub initial_access: vk_sync::AccessType,
+
+    /// Access type the image is transitioned to at the end of the frame.
+    pub final_access: vk_sync::AccessType,
+
+    /// Waited upon before the presentation passes write to the image.
+    pub wait_semaphore: Option<vk::Semaphore>,
+
+    /// Signaled once the image is written and transitioned to `final_access`.
+    pub signal_semaphore: Option<vk::Semaphore>,
+}
+
+enum FrameOutput<'a> {
+    Swapchain(&'a mut Swapchain),
+    External(ExternalImageTarget),
+}
+
+/// Unrecoverable failures reported by `draw_frame` and `render_to_image`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderError {
+    /// The GPU device has been lost, e.g. due to a shader crash or a driver reset.
+    /// The last crash marker (pass or pipeline) has already been logged by then.
+    /// All GPU resources are invalid; the renderer must not be used for further frames.
+    DeviceLost,
+
+    /// The window surface has been lost, e.g. on display hot-plug. The frame was dropped,
+    /// but the renderer remains usable once the surface is recreated with
+    /// `RenderBackend::recreate_surface`.
+    SurfaceLost,
+
+    /// The swapchain extent doesn't match the one the frame was recorded for, e.g. due to
+    /// a race with a window resize. The frame was dropped without presenting, but
+    /// the renderer remains usable.
+    ///
+    /// This is checked against the swapchain's current extent before acquiring an image,
+    /// since acquired images must be presented. The extent of the acquired image itself
+    /// always matches the swapchain's.
+    OutputExtentMismatch {
+        expected: [u32; 2],
+        actual: [u32; 2],
+    },
+
+    /// Acquiring a swapchain image failed with `ERROR_OUT_OF_DATE_KHR`, e.g. due to a resize
+    /// after the extent was checked. The frame was dropped without presenting, and
+    /// the renderer remains usable once the swapchain is rebuilt with
+    /// `RenderBackend::recreate_swapchain`.
+    SwapchainOutOfDate,
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::DeviceLost => write!(f, "the GPU device has been lost"),
+            RenderError::SurfaceLost => write!(f, "the window surface has been lost"),
+            RenderError::OutputExtentMismatch { expected, actual } => write!(
+                f,
+                "the frame was recorded for a {}x{} output, but the swapchain is {}x{}",
+                expected[0], expected[1], actual[0], actual[1]
+            ),
+            RenderError::SwapchainOutOfDate => write!(f, "the swapchain is out of date"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+fn render_error_from_backend(err: BackendError, what: &str) -> RenderError {
+    match err {
+        BackendError::Vulkan {
+            err: vk::Result::ERROR_DEVICE_LOST,
+            ..
+        } => RenderError::DeviceLost,
+        BackendError::Vulkan {
+            err: vk::Result::ERROR_SURFACE_LOST_KHR,
+            ..
+        } => RenderError::SurfaceLost,
+        err => panic!("{} failed: {:?}", what, err),
+    }
+}
+
 pub struct FrameConstantsLayout {
     pub globals_offset: u32,
     pub instance_dynamic_parameters_offset: u32,
     pub triangle_lights_offset: u32,
+
+    /// Frame constants of additional views, used by passes with `PassBuilder::set_view`.
+    /// View `n` uses element `n - 1`.
+    pub view_globals_offsets: Vec<u32>,
+}
+
+impl FrameConstantsLayout {
+    pub fn globals_offset_for_view(&self, view_index: u32) -> u32 {
+        match view_index {
+            0 => self.globals_offset,
+            _ => *self
+                .view_globals_offsets
+                .get(view_index as usize - 1)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "No frame constants for view {}; only {} additional views were provided",
+                        view_index,
+                        self.view_globals_offsets.len()
+                    )
+                }),
+        }
+    }
 }
 
 impl Renderer {
     pub fn new(backend: &RenderBackend) -> anyhow::Result<Self> {
-        let dynamic_constants = DynamicConstants::new({
+        Self::with_dynamic_constants_size(backend, DYNAMIC_CONSTANTS_SIZE_BYTES)
+    }
+
+    /// Like `new`, but with a custom size of the per-frame dynamic constants region.
+    /// The backing buffer holds one such region for each frame in flight.
+    pub fn with_dynamic_constants_size(
+        backend: &RenderBackend,
+        frame_size_bytes: usize,
+    ) -> anyhow::Result<Self> {
+        let limits = &backend.device.physical_device().properties.limits;
+
+        anyhow::ensure!(
+            MAX_DYNAMIC_CONSTANTS_BYTES_PER_DISPATCH <= limits.max_uniform_buffer_range as usize,
+            "maxUniformBufferRange ({}) is too small for dynamic constants",
+            limits.max_uniform_buffer_range
+        );
+        anyhow::ensure!(
+            MAX_DYNAMIC_CONSTANTS_STORAGE_BUFFER_BYTES <= limits.max_storage_buffer_range as usize,
+            "maxStorageBufferRange ({}) is too small for dynamic storage buffers",
+            limits.max_storage_buffer_range
+        );
+        anyhow::ensure!(
+            frame_size_bytes >= MAX_DYNAMIC_CONSTANTS_STORAGE_BUFFER_BYTES,
+            "Dynamic constants size ({} bytes) must be at least {} bytes",
+            frame_size_bytes,
+            MAX_DYNAMIC_CONSTANTS_STORAGE_BUFFER_BYTES
+        );
+
+        let dynamic_constants = DynamicConstants::new(
             backend.device.create_buffer(
                 BufferDesc::new_cpu_to_gpu(
-                    DYNAMIC_CONSTANTS_SIZE_BYTES * DYNAMIC_CONSTANTS_BUFFER_COUNT,
+                    frame_size_bytes * DYNAMIC_CONSTANTS_BUFFER_COUNT,
                     vk::BufferUsageFlags::UNIFORM_BUFFER
                         | vk::BufferUsageFlags::STORAGE_BUFFER
                         | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
                 ),
                 "dynamic constants buffer",
                 None,
-            )?
-        });
+            )?,
+            frame_size_bytes,
+        );
 
         let frame_descriptor_set =
             Self::create_frame_descriptor_set(backend, &dynamic_constants.buffer);
@@ -110,6 +290,19 @@ impl Renderer {
 
             compiled_rg: None,
             temporal_rg_state: Default::default(),
+
+            last_frame_stats: None,
+            pending_screenshot: None,
+            pending_frame_capture: false,
+            captured_frame: None,
+
+            renderdoc: RenderDoc::load(),
+            pending_capture_frames: 0,
+
+            frame_scratch: FrameScratchAllocator::new(&backend.device, FRAME_SCRATCH_SIZE_BYTES)?,
+
+            low_latency: false,
+            output_dims: None,
         })
     }
 
@@ -117,18 +310,80 @@ impl Renderer {
         &mut self,
         prepare_frame_constants: PrepareFrameConstantsFn,
         swapchain: &mut Swapchain,
-    ) where
+    ) -> Result<(), RenderError>
+    where
+        PrepareFrameConstantsFn: FnOnce(&mut DynamicConstants) -> FrameConstantsLayout,
+    {
+        self.draw_frame_impl(prepare_frame_constants, FrameOutput::Swapchain(swapchain))
+    }
+
+    /// Like `draw_frame`, but writes the presentation output to a caller-supplied image
+    /// instead of the swapchain. Synchronization with the image's owner is the caller's
+    /// responsibility, via the semaphores in `target`.
+    pub fn render_to_image<PrepareFrameConstantsFn>(
+        &mut self,
+        prepare_frame_constants: PrepareFrameConstantsFn,
+        target: ExternalImageTarget,
+    ) -> Result<(), RenderError>
+    where
+        PrepareFrameConstantsFn: FnOnce(&mut DynamicConstants) -> FrameConstantsLayout,
+    {
+        self.draw_frame_impl(prepare_frame_constants, FrameOutput::External(target))
+    }
+
+    fn draw_frame_impl<PrepareFrameConstantsFn>(
+        &mut self,
+        prepare_frame_constants: PrepareFrameConstantsFn,
+        output: FrameOutput,
+    ) -> Result<(), RenderError>
+    where
         PrepareFrameConstantsFn: FnOnce(&mut DynamicConstants) -> FrameConstantsLayout,
     {
         let rg = if let Some(rg) = self.compiled_rg.take() {
             rg
         } else {
-            return;
+            return Ok(());
         };
 
+        // Captures span everything from here until after presentation, and are ended
+        // even if the frame is skipped or fails.
+        let renderdoc_capture = self.pending_capture_frames > 0;
+        if let (Some(renderdoc), true) = (&self.renderdoc, renderdoc_capture) {
+            renderdoc.start_frame_capture();
+        }
+
+        let result = self.draw_compiled_frame(rg, prepare_frame_constants, output);
+
+        if let (Some(renderdoc), true) = (&self.renderdoc, renderdoc_capture) {
+            if !renderdoc.end_frame_capture() {
+                warn!("RenderDoc frame capture failed");
+            }
+            self.pending_capture_frames -= 1;
+        }
+
+        result
+    }
+
+    fn draw_compiled_frame<PrepareFrameConstantsFn>(
+        &mut self,
+        rg: CompiledRenderGraph,
+        prepare_frame_constants: PrepareFrameConstantsFn,
+        mut output: FrameOutput,
+    ) -> Result<(), RenderError>
+    where
+        PrepareFrameConstantsFn: FnOnce(&mut DynamicConstants) -> FrameConstantsLayout,
+    {
+        let frame_start = Instant::now();
+        let pass_count = rg.pass_count();
+        let expected_swap_chain_extent = rg.swap_chain_extent();
+
         let device = &*self.device;
         let raw_device = &device.raw;
 
+        if self.low_latency {
+            device.wait_for_previous_frame();
+        }
+
         let current_frame = self.device.begin_frame();
 
         // Both command buffers are accessible now, so begin recording.
@@ -174,6 +429,7 @@ impl Renderer {
                         frame_descriptor_set: self.frame_descriptor_set,
                         frame_constants_layout,
                         profiler_data: &current_frame.profiler_data,
+                        thread_command_pools: &current_frame.thread_command_pools,
                     },
                     &mut self.transient_resource_cache,
                     &mut self.dynamic_constants,
@@ -208,18 +464,84 @@ impl Renderer {
                         &submit_info,
                         main_cb.submit_done_fence,
                     )
-                    .map_err(|err| device.report_error(err.into()))
-                    .expect("main queue_submit failed");
+                    .map_err(|err| {
+                        render_error_from_backend(
+                            device.report_error(err.into()),
+                            "main queue_submit",
+                        )
+                    })?;
             };
         }
 
         // Now that we've done the main submission and the GPU is busy, acquire the presentation image.
         // This can block, so we're doing it as late as possible.
 
-        let swapchain_image = swapchain
-            .acquire_next_image()
-            .ok()
-            .expect("swapchain image");
+        let acquire_start = Instant::now();
+        let mut swapchain_image = None;
+        let (output_image, initial_access, final_access, wait_semaphore, signal_semaphore) =
+            match &mut output {
+                FrameOutput::Swapchain(swapchain) => {
+                    // Check before acquiring, as acquired images must be presented.
+                    let acquired = match expected_swap_chain_extent {
+                        Some(expected) if expected != swapchain.extent() => {
+                            Err(RenderError::OutputExtentMismatch {
+                                expected,
+                                actual: swapchain.extent(),
+                            })
+                        }
+                        _ => match swapchain.acquire_next_image() {
+                            Ok(image) => Ok(image),
+                            Err(SwapchainAcquireImageErr::DeviceLost) => {
+                                return Err(RenderError::DeviceLost)
+                            }
+                            Err(SwapchainAcquireImageErr::SurfaceLost) => {
+                                Err(RenderError::SurfaceLost)
+                            }
+                            Err(SwapchainAcquireImageErr::RecreateFramebuffer) => {
+                                Err(RenderError::SwapchainOutOfDate)
+                            }
+                        },
+                    };
+
+                    let image = match acquired {
+                        Ok(image) => image,
+                        Err(err) => {
+                            // The main command buffer has been submitted, but there's nothing
+                            // to present to. Drop the rest of the frame, keeping the renderer usable.
+                            executing_rg.release_resources_without_presentation(
+                                &mut self.transient_resource_cache,
+                            );
+                            self.abandon_temporal_state();
+                            self.dynamic_constants.advance_frame();
+                            self.frame_scratch.advance_frame();
+                            self.device.finish_frame(current_frame);
+                            return Err(err);
+                        }
+                    };
+
+                    let res = (
+                        image.image.clone(),
+                        vk_sync::AccessType::Present,
+                        vk_sync::AccessType::Present,
+                        Some(image.acquire_semaphore),
+                        Some(image.rendering_finished_semaphore),
+                    );
+                    swapchain_image = Some(image);
+                    res
+                }
+                FrameOutput::External(target) => (
+                    target.image.clone(),
+                    target.initial_access,
+                    target.final_access,
+                    target.wait_semaphore,
+                    target.signal_semaphore,
+                ),
+            };
+
+        let swapchain_acquire_time = acquire_start.elapsed();
+        self.output_dims = Some([output_image.desc.extent[0], output_image.desc.extent[1]]);
+
+        let mut present_result = Ok(());
 
         // Execute the rest of the render graph, and submit the presentation command buffer.
         let retired_rg = {
@@ -227,13 +549,13 @@ impl Renderer {
 
             let presentation_cb = &current_frame.presentation_command_buffer;
 
-            // Transition the swapchain to CS write
+            // Transition the output image to CS write
             vulkan::barrier::record_image_barrier(
                 device,
                 presentation_cb.raw,
                 vulkan::barrier::ImageBarrier::new(
-                    swapchain_image.image.raw,
-                    vk_sync::AccessType::Present,
+                    output_image.raw,
+                    initial_access,
                     vk_sync::AccessType::ComputeShaderWrite,
                     vk::ImageAspectFlags::COLOR,
                 )
@@ -241,16 +563,46 @@ impl Renderer {
             );
 
             let retired_rg =
-                executing_rg.record_presentation_cb(presentation_cb, swapchain_image.image.clone());
+                executing_rg.record_presentation_cb(presentation_cb, output_image.clone());
+
+            let screenshot_path = self.pending_screenshot.take();
+            let capture_frame = std::mem::take(&mut self.pending_frame_capture);
+
+            let output_access = retired_rg.swapchain_access_type();
+            let readback = if screenshot_path.is_some() || capture_frame {
+                match screenshot::record_readback(
+                    device,
+                    presentation_cb,
+                    &output_image,
+                    output_access,
+                ) {
+                    Ok(buffer) => Some(buffer),
+                    Err(err) => {
+                        if let Some(path) = &screenshot_path {
+                            error!("Could not save screenshot to {:?}: {:#}", path, err);
+                        }
+                        if capture_frame {
+                            self.captured_frame = Some(Err(err));
+                        }
+                        None
+                    }
+                }
+            } else {
+                None
+            };
 
-            // Transition the swapchain to present
+            // Transition the output image to its final access type (present for the swapchain)
             vulkan::barrier::record_image_barrier(
                 device,
                 presentation_cb.raw,
                 vulkan::barrier::ImageBarrier::new(
-                    swapchain_image.image.raw,
-                    vk_sync::AccessType::ComputeShaderWrite,
-                    vk_sync::AccessType::Present,
+                    output_image.raw,
+                    if readback.is_some() {
+                        vk_sync::AccessType::TransferRead
+                    } else {
+                        output_access
+                    },
+                    final_access,
                     vk::ImageAspectFlags::COLOR,
                 ),
             );
@@ -263,12 +615,24 @@ impl Renderer {
             unsafe {
                 raw_device.end_command_buffer(presentation_cb.raw).unwrap();
 
+                let wait_semaphores: &[vk::Semaphore] = match &wait_semaphore {
+                    Some(semaphore) => std::slice::from_ref(semaphore),
+                    None => &[],
+                };
+                let signal_semaphores: &[vk::Semaphore] = match &signal_semaphore {
+                    Some(semaphore) => std::slice::from_ref(semaphore),
+                    None => &[],
+                };
+                let wait_dst_stage_mask: &[vk::PipelineStageFlags] = if wait_semaphore.is_some() {
+                    &[vk::PipelineStageFlags::COMPUTE_SHADER]
+                } else {
+                    &[]
+                };
+
                 let submit_info = [vk::SubmitInfo::builder()
-                    .wait_semaphores(std::slice::from_ref(&swapchain_image.acquire_semaphore))
-                    .signal_semaphores(std::slice::from_ref(
-                        &swapchain_image.rendering_finished_semaphore,
-                    ))
-                    .wait_dst_stage_mask(&[vk::PipelineStageFlags::COMPUTE_SHADER])
+                    .wait_semaphores(wait_semaphores)
+                    .signal_semaphores(signal_semaphores)
+                    .wait_dst_stage_mask(wait_dst_stage_mask)
                     .command_buffers(std::slice::from_ref(&presentation_cb.raw))
                     .build()];
                 raw_device
@@ -282,11 +646,48 @@ impl Renderer {
                         &submit_info,
                         presentation_cb.submit_done_fence,
                     )
-                    .map_err(|err| device.report_error(err.into()))
-                    .expect("presentation queue_submit failed");
+                    .map_err(|err| {
+                        render_error_from_backend(
+                            device.report_error(err.into()),
+                            "presentation queue_submit",
+                        )
+                    })?;
+            }
+
+            if let Some(buffer) = readback {
+                let captured = screenshot::finish_readback(
+                    device,
+                    presentation_cb,
+                    &output_image.desc,
+                    buffer,
+                );
+
+                if let Some(path) = screenshot_path {
+                    let saved = match &captured {
+                        Ok(image) => image.save_png(&path),
+                        Err(err) => Err(anyhow::anyhow!("{:#}", err)),
+                    };
+
+                    match saved {
+                        Ok(()) => info!("Saved screenshot to {:?}", path),
+                        Err(err) => error!("Could not save screenshot to {:?}: {:#}", path, err),
+                    }
+                }
+
+                if capture_frame {
+                    self.captured_frame = Some(captured);
+                }
             }
 
-            swapchain.present_image(swapchain_image);
+            // Errors are reported after retiring the frame, so that the renderer
+            // stays usable after recoverable ones.
+            if let (FrameOutput::Swapchain(swapchain), Some(swapchain_image)) =
+                (output, swapchain_image)
+            {
+                present_result = swapchain
+                    .present_image(swapchain_image)
+                    .map_err(|err| render_error_from_backend(err, "present"));
+            }
 
             retired_rg
         };
@@ -300,8 +701,59 @@ impl Renderer {
 
         retired_rg.release_resources(&mut self.transient_resource_cache);
 
+        self.last_frame_stats = Some(FrameStats {
+            cpu_record_time: frame_start.elapsed().saturating_sub(swapchain_acquire_time),
+            gpu_frame_time: kajiya_backend::gpu_profiler::profiler()
+                .last_report()
+                .map(|report| {
+                    Duration::from_secs_f64(
+                        report
+                            .scopes
+                            .iter()
+                            .map(|scope| scope.duration.ms())
+                            .sum::<f64>()
+                            / 1000.0,
+                    )
+                }),
+            swapchain_acquire_time,
+            pass_count,
+            dynamic_constants_bytes: self.dynamic_constants.frame_bytes_used(),
+        });
+
         self.dynamic_constants.advance_frame();
+        self.frame_scratch.advance_frame();
         self.device.finish_frame(current_frame);
+
+        present_result
+    }
+
+    /// Returns temporal resources of an exported graph which won't be retired back to the
+    /// inert state. Their contents are kept, but the access types are not known any more.
+    fn abandon_temporal_state(&mut self) {
+        self.temporal_rg_state = match std::mem::take(&mut self.temporal_rg_state) {
+            TemporalRg::Inert(state) => TemporalRg::Inert(state),
+            TemporalRg::Exported(ExportedTemporalRenderGraphState(mut state)) => {
+                state.resources = state
+                    .resources
+                    .into_iter()
+                    .map(|(key, res)| {
+                        let res = match res {
+                            res @ TemporalResourceState::Inert { .. } => res,
+                            TemporalResourceState::Imported { resource, .. }
+                            | TemporalResourceState::Exported { resource, .. } => {
+                                TemporalResourceState::Inert {
+                                    resource,
+                                    access_type: vk_sync::AccessType::Nothing,
+                                }
+                            }
+                        };
+                        (key, res)
+                    })
+                    .collect();
+
+                TemporalRg::Inert(state)
+            }
+        };
     }
 
     // Descriptor set for per-frame data
@@ -452,6 +904,8 @@ impl Renderer {
             },
         );
 
+        self.frame_scratch.begin_frame(&mut rg);
+
         prepare_render_graph(&mut rg);
         let (rg, temporal_rg_state) = rg.export_temporal();
 
@@ -498,6 +952,125 @@ impl Renderer {
         }
     }
 
+    /// Compiles every pipeline registered so far, blocking until done. Pipelines are registered
+    /// as render graphs get compiled, so call this after `prepare_frame` to warm up
+    /// all the pipelines a frame needs before rendering it.
+    pub fn compile_all_pipelines(
+        &mut self,
+        on_progress: impl FnMut(PipelineCompileProgress),
+    ) -> anyhow::Result<()> {
+        self.pipeline_cache.compile_all(&self.device, on_progress)
+    }
+
+    /// See `PipelineCache::status`.
+    pub fn pipeline_status(&self, handle: impl Into<PipelineHandle>) -> PipelineStatus {
+        self.pipeline_cache.status(handle)
+    }
+
+    /// See `PipelineCache::clear_error`.
+    pub fn clear_pipeline_error(&mut self, handle: impl Into<PipelineHandle>) {
+        self.pipeline_cache.clear_error(handle);
+    }
+
+    /// See `PipelineCache::failed_pipelines`.
+    pub fn failed_pipelines(&self) -> impl Iterator<Item = (PipelineHandle, &str, &str)> {
+        self.pipeline_cache.failed_pipelines()
+    }
+
+    /// See `PipelineCache::set_shader_dump_dir`.
+    pub fn set_shader_dump_dir(&mut self, dir: Option<PathBuf>) {
+        self.pipeline_cache.set_shader_dump_dir(dir);
+    }
+
+    /// See `PipelineCache::load_from_disk`.
+    pub fn load_pipeline_cache(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        self.pipeline_cache.load_from_disk(&self.device, path)
+    }
+
+    /// See `PipelineCache::save_to_disk`.
+    pub fn save_pipeline_cache(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        self.pipeline_cache.save_to_disk(&self.device, path)
+    }
+
+    /// Saves the output of the next `draw_frame` or `render_to_image` call as an 8-bit
+    /// sRGB PNG. The output is already tonemapped and display-encoded by the presentation
+    /// passes, so the file matches what ends up on screen. Float outputs are treated as
+    /// linear, and are clamped and sRGB-encoded.
+    ///
+    /// That frame waits for the GPU to finish before returning.
+    pub fn save_screenshot(&mut self, path: impl Into<PathBuf>) {
+        self.pending_screenshot = Some(path.into());
+    }
+
+    /// Reads back the output of the next `draw_frame` or `render_to_image` call, to be
+    /// retrieved with `capture_frame` once it returns. That frame waits for the GPU
+    /// to finish before returning.
+    pub fn capture_next_frame(&mut self) {
+        self.pending_frame_capture = true;
+        self.captured_frame = None;
+    }
+
+    /// Returns the frame read back after `capture_next_frame`. Fails if no capture was
+    /// requested, if the frame hasn't been drawn yet, or if its output can't be read back,
+    /// e.g. a swapchain without `TRANSFER_SRC` usage.
+    pub fn capture_frame(&mut self) -> anyhow::Result<CapturedImage> {
+        match self.captured_frame.take() {
+            Some(captured) => captured,
+            None if self.pending_frame_capture => {
+                anyhow::bail!("The frame to capture hasn't been drawn yet")
+            }
+            None => anyhow::bail!("No frame capture was requested with capture_next_frame"),
+        }
+    }
+
+    /// Captures the next `frame_count` frames in RenderDoc, one capture per frame.
+    /// Does nothing if the application isn't running under RenderDoc.
+    pub fn trigger_capture(&mut self, frame_count: u32) {
+        if self.renderdoc.is_some() {
+            self.pending_capture_frames += frame_count;
+        } else {
+            warn!("RenderDoc is not attached; ignoring the capture request");
+        }
+    }
+
+    /// When enabled, `draw_frame` and `render_to_image` wait for the GPU to finish the previous
+    /// frame before recording, instead of letting the CPU run a frame ahead. This lowers input
+    /// latency at the cost of throughput, as the CPU and GPU no longer overlap across frames.
+    pub fn set_low_latency(&mut self, low_latency: bool) {
+        self.low_latency = low_latency;
+    }
+
+    pub fn low_latency(&self) -> bool {
+        self.low_latency
+    }
+
+    /// Extent of the image the most recent frame was output to: the swapchain image
+    /// for `draw_frame`, or the target of `render_to_image`.
+    pub fn output_dims(&self) -> Option<[u32; 2]> {
+        self.output_dims
+    }
+
+    /// Statistics of the most recent `draw_frame` or `render_to_image` call.
+    pub fn last_frame_stats(&self) -> Option<&FrameStats> {
+        self.last_frame_stats.as_ref()
+    }
+
+    /// GPU time of each render graph pass in milliseconds, in recording order, named after
+    /// the passes. Every pass is wrapped in timestamp queries; like `FrameStats::gpu_frame_time`,
+    /// the timings come from the most recent profiler report, a few frames behind the CPU.
+    pub fn gpu_pass_timings(&self) -> Vec<(String, f64)> {
+        kajiya_backend::gpu_profiler::profiler()
+            .last_report()
+            .map(|report| {
+                report
+                    .scopes
+                    .iter()
+                    .map(|scope| (scope.name.clone(), scope.duration.ms()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn device(&self) -> &Arc<Device> {
         &self.device
     }
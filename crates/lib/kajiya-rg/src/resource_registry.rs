@@ -148,4 +148,16 @@ impl<'exec_params, 'constants> ResourceRegistry<'exec_params, 'constants> {
         let handle = self.pipelines.rt[pipeline.id];
         self.execution_params.pipeline_cache.get_ray_tracing(handle)
     }
+
+    pub(crate) fn compute_pipeline_name(&self, pipeline: RgComputePipelineHandle) -> &str {
+        let handle = self.pipelines.compute[pipeline.id];
+        self.execution_params.pipeline_cache.compute_name(handle)
+    }
+
+    pub(crate) fn ray_tracing_pipeline_name(&self, pipeline: RgRtPipelineHandle) -> &str {
+        let handle = self.pipelines.rt[pipeline.id];
+        self.execution_params
+            .pipeline_cache
+            .ray_tracing_name(handle)
+    }
 }
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use kajiya_backend::{
+    ash::vk,
+    dynamic_constants::DYNAMIC_CONSTANTS_BUFFER_COUNT,
+    vk_sync::AccessType,
+    vulkan::buffer::{Buffer, BufferDesc},
+    Device,
+};
+
+use crate::{Handle, RenderGraph};
+
+/// Default size of the per-frame scratch region.
+pub const FRAME_SCRATCH_SIZE_BYTES: usize = 1024 * 1024 * 16;
+
+// Must be >= `minStorageBufferOffsetAlignment`. In practice <= 256.
+const FRAME_SCRATCH_ALIGNMENT: usize = 256;
+
+/// GPU-only buffer space for data which only lives for one frame, e.g. intermediate
+/// results of compute passes, or generated vertices and indices.
+///
+/// Like dynamic constants, the buffer holds one region per frame in flight, and
+/// allocations are reclaimed wholesale when the region comes around again.
+pub struct FrameScratchAllocator {
+    buffer: Arc<Buffer>,
+    frame_size_bytes: usize,
+    frame_parity: usize,
+}
+
+impl FrameScratchAllocator {
+    pub fn new(device: &Device, frame_size_bytes: usize) -> anyhow::Result<Self> {
+        let buffer = device.create_buffer(
+            BufferDesc::new_gpu_only(
+                frame_size_bytes * DYNAMIC_CONSTANTS_BUFFER_COUNT,
+                vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::UNIFORM_TEXEL_BUFFER
+                    | vk::BufferUsageFlags::VERTEX_BUFFER
+                    | vk::BufferUsageFlags::INDEX_BUFFER
+                    | vk::BufferUsageFlags::INDIRECT_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_SRC
+                    | vk::BufferUsageFlags::TRANSFER_DST,
+            ),
+            "frame scratch buffer",
+            None,
+        )?;
+
+        Ok(Self {
+            buffer: Arc::new(buffer),
+            frame_size_bytes,
+            frame_parity: 0,
+        })
+    }
+
+    /// Makes the current frame's region available to `rg` via `RenderGraph::alloc_scratch`.
+    pub(crate) fn begin_frame(&self, rg: &mut RenderGraph) {
+        rg.frame_scratch = Some(FrameScratchRegion {
+            buffer: self.buffer.clone(),
+            handle: None,
+            base_offset: self.frame_parity * self.frame_size_bytes,
+            size: self.frame_size_bytes,
+            used: 0,
+        });
+    }
+
+    pub(crate) fn advance_frame(&mut self) {
+        self.frame_parity = (self.frame_parity + 1) % DYNAMIC_CONSTANTS_BUFFER_COUNT;
+    }
+}
+
+pub(crate) struct FrameScratchRegion {
+    buffer: Arc<Buffer>,
+
+    // Imported on first allocation; all allocations share it.
+    handle: Option<Handle<Buffer>>,
+
+    base_offset: usize,
+    size: usize,
+    used: usize,
+}
+
+/// A range of the frame scratch buffer. Bind it with `bind_range(offset, size)`
+/// on the pass reference to `buffer`.
+///
+/// All allocations in a frame share the same graph resource, so passes using
+/// different allocations are still synchronized against each other.
+pub struct ScratchBuffer {
+    pub buffer: Handle<Buffer>,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl RenderGraph {
+    /// Allocates `size_bytes` of GPU-only memory, valid until the end of the frame.
+    /// Contents are undefined until written.
+    pub fn alloc_scratch(&mut self, size_bytes: usize) -> ScratchBuffer {
+        let mut scratch = self
+            .frame_scratch
+            .take()
+            .expect("Frame scratch memory is only available in graphs prepared by the Renderer");
+
+        assert!(
+            scratch.used + size_bytes <= scratch.size,
+            "Out of frame scratch memory: {} of {} bytes used, {} more requested",
+            scratch.used,
+            scratch.size,
+            size_bytes
+        );
+
+        let handle = match &scratch.handle {
+            Some(handle) => handle.clone_unchecked(),
+            None => {
+                let handle = self.import(scratch.buffer.clone(), AccessType::Nothing);
+                scratch.handle = Some(handle.clone_unchecked());
+                handle
+            }
+        };
+
+        let offset = scratch.base_offset + scratch.used;
+        scratch.used += (size_bytes + FRAME_SCRATCH_ALIGNMENT - 1) & !(FRAME_SCRATCH_ALIGNMENT - 1);
+        self.frame_scratch = Some(scratch);
+
+        ScratchBuffer {
+            buffer: handle,
+            offset: offset as u64,
+            size: size_bytes as u64,
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::Path;
+
+use kajiya_backend::{
+    ash::vk,
+    vk_sync::AccessType,
+    vulkan::{
+        barrier::{record_image_barrier, ImageBarrier},
+        buffer::{Buffer, BufferDesc},
+        device::CommandBuffer,
+        image::{Image, ImageDesc},
+    },
+    Device,
+};
+
+fn bytes_per_pixel(format: vk::Format) -> Option<usize> {
+    match format {
+        vk::Format::B8G8R8A8_UNORM
+        | vk::Format::B8G8R8A8_SRGB
+        | vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SRGB => Some(4),
+        vk::Format::R16G16B16A16_SFLOAT => Some(8),
+        _ => None,
+    }
+}
+
+/// Copies `image` to a new CPU-visible buffer. Expects the image in `access_type`,
+/// and leaves it in `TransferRead`.
+pub(crate) fn record_readback(
+    device: &Device,
+    cb: &CommandBuffer,
+    image: &Image,
+    access_type: AccessType,
+) -> anyhow::Result<Buffer> {
+    let bytes_per_pixel = bytes_per_pixel(image.desc.format)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported screenshot format {:?}", image.desc.format))?;
+
+    anyhow::ensure!(
+        image.desc.usage.contains(vk::ImageUsageFlags::TRANSFER_SRC),
+        "The output image was not created with TRANSFER_SRC usage"
+    );
+
+    let [width, height, _] = image.desc.extent;
+    let buffer = device.create_buffer(
+        BufferDesc::new_gpu_to_cpu(
+            width as usize * height as usize * bytes_per_pixel,
+            vk::BufferUsageFlags::TRANSFER_DST,
+        ),
+        "screenshot readback",
+        None,
+    )?;
+
+    record_image_barrier(
+        device,
+        cb.raw,
+        ImageBarrier::new(
+            image.raw,
+            access_type,
+            AccessType::TransferRead,
+            vk::ImageAspectFlags::COLOR,
+        ),
+    );
+
+    unsafe {
+        device.raw.cmd_copy_image_to_buffer(
+            cb.raw,
+            image.raw,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            buffer.raw,
+            &[vk::BufferImageCopy {
+                buffer_offset: 0,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    mip_level: 0,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                },
+                image_offset: vk::Offset3D::default(),
+                image_extent: vk::Extent3D {
+                    width,
+                    height,
+                    depth: 1,
+                },
+            }],
+        );
+    }
+
+    Ok(buffer)
+}
+
+/// Waits for `cb` to finish, and copies the contents of `buffer` out of it.
+pub(crate) fn finish_readback(
+    device: &Device,
+    cb: &CommandBuffer,
+    desc: &ImageDesc,
+    buffer: Buffer,
+) -> anyhow::Result<CapturedImage> {
+    unsafe {
+        device.raw.wait_for_fences(
+            std::slice::from_ref(&cb.submit_done_fence),
+            true,
+            std::u64::MAX,
+        )
+    }
+    .map_err(|err| device.report_error(err.into()))?;
+
+    let data = buffer.allocation.mapped_slice().unwrap().to_vec();
+
+    // The GPU is done with the buffer, as the fence was waited on above.
+    device.immediate_destroy_buffer(buffer);
+
+    Ok(CapturedImage {
+        extent: [desc.extent[0], desc.extent[1]],
+        format: desc.format,
+        data,
+    })
+}
+
+/// Pixels of a frame read back with `Renderer::capture_next_frame`.
+pub struct CapturedImage {
+    pub extent: [u32; 2],
+
+    /// One of `B8G8R8A8_UNORM`, `B8G8R8A8_SRGB`, `R8G8B8A8_UNORM`, `R8G8B8A8_SRGB`,
+    /// or `R16G16B16A16_SFLOAT`.
+    pub format: vk::Format,
+
+    /// Tightly packed rows of `format` texels, top to bottom.
+    pub data: Vec<u8>,
+}
+
+impl CapturedImage {
+    /// Converts the pixels to 8-bit sRGB RGBA, e.g. for `image::RgbaImage`.
+    ///
+    /// 8-bit formats already hold display-encoded values, either written by the presentation
+    /// shaders, or encoded by the hardware for `_SRGB` formats. Float formats are treated as
+    /// linear, and are clamped and sRGB-encoded. Alpha is kept linear.
+    pub fn to_srgba8(&self) -> Vec<u8> {
+        match self.format {
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => self
+                .data
+                .chunks_exact(4)
+                .flat_map(|px| [px[2], px[1], px[0], px[3]])
+                .collect(),
+            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => self.data.clone(),
+            vk::Format::R16G16B16A16_SFLOAT => self
+                .data
+                .chunks_exact(8)
+                .flat_map(|px| {
+                    let channel = |i: usize| {
+                        half::f16::from_le_bytes([px[i * 2], px[i * 2 + 1]])
+                            .to_f32()
+                            .clamp(0.0, 1.0)
+                    };
+                    let encode = |v: f32| (v * 255.0 + 0.5) as u8;
+                    [
+                        encode(linear_to_srgb(channel(0))),
+                        encode(linear_to_srgb(channel(1))),
+                        encode(linear_to_srgb(channel(2))),
+                        encode(channel(3)),
+                    ]
+                })
+                .collect(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Writes the image as an 8-bit sRGB PNG, dropping alpha, which isn't meaningful
+    /// for most outputs.
+    pub fn save_png(&self, path: &Path) -> anyhow::Result<()> {
+        let rgb: Vec<u8> = self
+            .to_srgba8()
+            .chunks_exact(4)
+            .flat_map(|px| [px[0], px[1], px[2]])
+            .collect();
+
+        image::save_buffer(
+            path,
+            &rgb,
+            self.extent[0],
+            self.extent[1],
+            image::ColorType::Rgb8,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
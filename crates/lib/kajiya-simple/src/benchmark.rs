@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+/// Summary of a set of frame times.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameTimeStats {
+    pub min: Duration,
+    pub max: Duration,
+    pub avg: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+impl FrameTimeStats {
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        // Nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        Some(Self {
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            avg: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        })
+    }
+}
+
+impl std::fmt::Display for FrameTimeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "min {:.3} ms, avg {:.3} ms, max {:.3} ms, p50 {:.3} ms, p95 {:.3} ms, p99 {:.3} ms",
+            ms(self.min),
+            ms(self.avg),
+            ms(self.max),
+            ms(self.p50),
+            ms(self.p95),
+            ms(self.p99),
+        )
+    }
+}
+
+/// Results of `SimpleMainLoop::benchmark`.
+#[derive(Clone, Debug)]
+pub struct BenchReport {
+    pub frame_count: u32,
+
+    /// Sum of GPU pass timings per frame. Timestamps are read back a few frames late,
+    /// so this can hold fewer samples than `frame_count`.
+    pub gpu_frame_times: Vec<Duration>,
+
+    /// CPU wall-clock time per frame, including waits for the GPU to free up frame resources.
+    pub cpu_frame_times: Vec<Duration>,
+}
+
+impl BenchReport {
+    pub fn gpu(&self) -> Option<FrameTimeStats> {
+        FrameTimeStats::from_samples(&self.gpu_frame_times)
+    }
+
+    pub fn cpu(&self) -> Option<FrameTimeStats> {
+        FrameTimeStats::from_samples(&self.cpu_frame_times)
+    }
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} frames", self.frame_count)?;
+
+        match self.gpu() {
+            Some(gpu) => writeln!(f, "GPU: {}", gpu)?,
+            None => writeln!(f, "GPU: no timings available")?,
+        }
+
+        match self.cpu() {
+            Some(cpu) => write!(f, "CPU: {}", cpu),
+            None => write!(f, "CPU: no timings available"),
+        }
+    }
+}
@@ -1,19 +1,20 @@
 #![allow(dead_code)]
 
 use glam::Vec2;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 pub use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
 use winit::{
     dpi::PhysicalPosition,
-    event::{Event, WindowEvent},
+    event::{Event, MouseScrollDelta, WindowEvent},
 };
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct KeyState {
     pub ticks: u32,
 }
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct KeyboardState {
     keys_down: HashMap<VirtualKeyCode, KeyState>,
 }
@@ -54,13 +55,17 @@ impl KeyboardState {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct MouseState {
     pub physical_position: PhysicalPosition<f64>,
     pub delta: Vec2,
     pub buttons_held: u32,
     pub buttons_pressed: u32,
     pub buttons_released: u32,
+
+    /// Vertical wheel movement in lines, positive away from the user.
+    #[serde(default)]
+    pub wheel_delta: f32,
 }
 
 impl Default for MouseState {
@@ -71,6 +76,7 @@ impl Default for MouseState {
             buttons_held: 0,
             buttons_pressed: 0,
             buttons_released: 0,
+            wheel_delta: 0.0,
         }
     }
 }
@@ -80,6 +86,7 @@ impl MouseState {
         self.buttons_pressed = 0;
         self.buttons_released = 0;
         self.delta = Vec2::ZERO;
+        self.wheel_delta = 0.0;
 
         for event in events {
             match event {
@@ -103,6 +110,13 @@ impl MouseState {
                             self.buttons_released |= 1 << button_id;
                         }
                     }
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        self.wheel_delta += match delta {
+                            MouseScrollDelta::LineDelta(_, y) => *y,
+                            // Roughly one line per notch of a typical wheel
+                            MouseScrollDelta::PixelDelta(position) => position.y as f32 / 120.0,
+                        };
+                    }
                     _ => (),
                 },
                 Event::DeviceEvent {
@@ -0,0 +1,80 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::input::{KeyboardState, MouseState};
+
+/// Input state of a single frame, as seen by the app after updating it from window events.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordedInputFrame {
+    pub dt: f32,
+    pub keyboard: KeyboardState,
+    pub mouse: MouseState,
+}
+
+/// Writes per-frame input to a file, one frame per line, so that it can be replayed
+/// with `InputPlayback` for reproducible runs. Frames are flushed as they're recorded,
+/// so the recording survives the app being killed.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn create(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("Creating input recording {:?}", path))?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(
+        &mut self,
+        dt: f32,
+        keyboard: &KeyboardState,
+        mouse: &MouseState,
+    ) -> anyhow::Result<()> {
+        let frame = RecordedInputFrame {
+            dt,
+            keyboard: keyboard.clone(),
+            mouse: *mouse,
+        };
+
+        writeln!(self.writer, "{}", ron::ser::to_string(&frame)?)?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Reads back frames written by `InputRecorder`.
+pub struct InputPlayback {
+    lines: std::io::Lines<BufReader<File>>,
+}
+
+impl InputPlayback {
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).with_context(|| format!("Opening input recording {:?}", path))?;
+
+        Ok(Self {
+            lines: BufReader::new(file).lines(),
+        })
+    }
+
+    /// Returns `None` once the recording is exhausted.
+    pub fn next_frame(&mut self) -> anyhow::Result<Option<RecordedInputFrame>> {
+        match self.lines.next() {
+            Some(line) => Ok(Some(ron::de::from_str(&line?)?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -1,20 +1,28 @@
+mod benchmark;
 mod input;
+mod input_recording;
 mod main_loop;
+mod orbit_camera;
 
+pub use benchmark::*;
 pub use glam::*;
 pub use input::*;
+pub use input_recording::*;
 pub use kajiya::{
     backend::{
         file::{set_standard_vfs_mount_points, set_vfs_mount_point},
         *,
     },
     camera::*,
+    coordinate_system::*,
+    custom_pass::*,
     frame_desc::WorldFrameDesc,
     math::*,
-    world_renderer::{RenderDebugMode, RenderMode},
+    world_renderer::{QualityPreset, RenderDebugMode, RenderMode, TonemapOperator},
 };
 pub use log;
 pub use main_loop::*;
+pub use orbit_camera::*;
 pub use winit::{
     self,
     event::{ElementState, KeyboardInput, MouseButton, WindowEvent},
@@ -1,16 +1,26 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, sync::Arc, time::Instant};
 
 use kajiya::{
-    backend::{vulkan::RenderBackendConfig, *},
+    backend::{
+        ash::vk,
+        vulkan::{swapchain::SwapchainPresentPath, RenderBackendConfig},
+        *,
+    },
+    camera::CameraMatrices,
     frame_desc::WorldFrameDesc,
-    rg,
-    ui_renderer::UiRenderer,
+    rg::{
+        self,
+        renderer::{ExternalImageTarget, RenderError},
+    },
+    ui_renderer::{ScreenOverlay, UiRenderer},
     world_renderer::WorldRenderer,
 };
 
 #[cfg(feature = "dear-imgui")]
 use kajiya_imgui::ImGuiBackend;
 
+use crate::BenchReport;
+
 use turbosloth::*;
 
 use winit::{
@@ -23,10 +33,20 @@ use winit::{
 pub struct FrameContext<'a> {
     pub dt_filtered: f32,
     pub render_extent: [u32; 2],
+    /// Physical window extent in pixels, matching the swapchain.
+    pub output_extent: [u32; 2],
     pub events: &'a [Event<'static, ()>],
     pub world_renderer: &'a mut WorldRenderer,
     pub window: &'a winit::window::Window,
 
+    /// Composited over the final frame until replaced or reset to `None`.
+    pub overlay: &'a mut Option<ScreenOverlay>,
+
+    /// Strength of the contrast-adaptive sharpening applied while blitting the final image
+    /// to the window, in `[0, 1]`. Mostly useful when upscaling from a lower render resolution.
+    /// Zero, the default, disables sharpening. Persists across frames.
+    pub present_sharpening: &'a mut f32,
+
     #[cfg(feature = "dear-imgui")]
     pub imgui: Option<ImguiContext<'a>>,
 }
@@ -86,12 +106,17 @@ pub enum FullscreenMode {
 pub struct SimpleMainLoopBuilder {
     resolution: [u32; 2],
     vsync: bool,
+    desired_swapchain_image_count: u32,
+    swapchain_present_path: Option<SwapchainPresentPath>,
     fullscreen: Option<FullscreenMode>,
     graphics_debugging: bool,
     physical_device_index: Option<usize>,
     default_log_level: log::LevelFilter,
     window_scale: WindowScale,
     temporal_upsampling: f32,
+    present_sharpening: f32,
+    low_latency: bool,
+    worker_threads: Option<usize>,
 }
 
 impl Default for SimpleMainLoopBuilder {
@@ -105,12 +130,19 @@ impl SimpleMainLoopBuilder {
         SimpleMainLoopBuilder {
             resolution: [1280, 720],
             vsync: true,
+            // Triple-buffer so that acquiring an image doesn't stall for >16.6ms at 60Hz on AMD
+            // when frames take >16.6ms to render. Also allows MAILBOX to work.
+            desired_swapchain_image_count: 3,
+            swapchain_present_path: None,
             fullscreen: None,
             graphics_debugging: false,
             physical_device_index: None,
             default_log_level: log::LevelFilter::Warn,
             window_scale: WindowScale::SystemNative,
             temporal_upsampling: 1.0,
+            present_sharpening: 0.0,
+            low_latency: false,
+            worker_threads: None,
         }
     }
 
@@ -124,6 +156,22 @@ impl SimpleMainLoopBuilder {
         self
     }
 
+    /// Clamped to the image count range supported by the surface.
+    pub fn desired_swapchain_image_count(mut self, desired_swapchain_image_count: u32) -> Self {
+        self.desired_swapchain_image_count = desired_swapchain_image_count;
+        self
+    }
+
+    /// Force a specific way of getting images into the swapchain, if supported.
+    /// By default, blits are preferred over compute writes.
+    pub fn swapchain_present_path(
+        mut self,
+        swapchain_present_path: Option<SwapchainPresentPath>,
+    ) -> Self {
+        self.swapchain_present_path = swapchain_present_path;
+        self
+    }
+
     pub fn graphics_debugging(mut self, graphics_debugging: bool) -> Self {
         self.graphics_debugging = graphics_debugging;
         self
@@ -158,6 +206,26 @@ impl SimpleMainLoopBuilder {
         self
     }
 
+    /// Sharpening applied when blitting to the swapchain, in `[0, 1]`.
+    /// See `FrameContext::present_sharpening`.
+    pub fn present_sharpening(mut self, present_sharpening: f32) -> Self {
+        self.present_sharpening = present_sharpening;
+        self
+    }
+
+    /// See `Renderer::set_low_latency`.
+    pub fn low_latency(mut self, low_latency: bool) -> Self {
+        self.low_latency = low_latency;
+        self
+    }
+
+    /// Number of threads evaluating shader compilation and asset loading. If `None`, uses
+    /// the `SMOL_THREADS` environment variable, or `default_worker_thread_count`.
+    pub fn worker_threads(mut self, worker_threads: Option<usize>) -> Self {
+        self.worker_threads = worker_threads;
+        self
+    }
+
     pub fn build(self, window_builder: WindowBuilder) -> anyhow::Result<SimpleMainLoop> {
         SimpleMainLoop::build(self, window_builder)
     }
@@ -174,6 +242,7 @@ pub struct SimpleMainLoop {
     render_backend: RenderBackend,
     rg_renderer: kajiya::rg::renderer::Renderer,
     render_extent: [u32; 2],
+    present_sharpening: f32,
 }
 
 impl SimpleMainLoop {
@@ -186,7 +255,7 @@ impl SimpleMainLoop {
         mut window_builder: WindowBuilder,
     ) -> anyhow::Result<Self> {
         kajiya::logging::set_up_logging(builder.default_log_level)?;
-        std::env::set_var("SMOL_THREADS", "64"); // HACK; TODO: get a real executor
+        kajiya::backend::executor::init_worker_threads(builder.worker_threads);
 
         // Note: asking for the logical size means that if the OS is using DPI scaling,
         // we'll get a physically larger window (with more pixels).
@@ -245,6 +314,8 @@ impl SimpleMainLoop {
             RenderBackendConfig {
                 swapchain_extent,
                 vsync: builder.vsync,
+                desired_swapchain_image_count: builder.desired_swapchain_image_count,
+                swapchain_present_path: builder.swapchain_present_path,
                 graphics_debugging: builder.graphics_debugging,
                 device_index: builder.physical_device_index,
             },
@@ -259,7 +330,8 @@ impl SimpleMainLoop {
         )?;
         let ui_renderer = UiRenderer::default();
 
-        let rg_renderer = kajiya::rg::renderer::Renderer::new(&render_backend)?;
+        let mut rg_renderer = kajiya::rg::renderer::Renderer::new(&render_backend)?;
+        rg_renderer.set_low_latency(builder.low_latency);
 
         #[cfg(feature = "dear-imgui")]
         let mut imgui = imgui::Context::create();
@@ -298,11 +370,113 @@ impl SimpleMainLoop {
             render_backend,
             rg_renderer,
             render_extent,
+            present_sharpening: builder.present_sharpening,
         })
     }
 
     pub fn window_aspect_ratio(&self) -> f32 {
-        self.window.inner_size().width as f32 / self.window.inner_size().height as f32
+        let size = self.window.inner_size();
+        size.width.max(1) as f32 / size.height.max(1) as f32
+    }
+
+    /// Renders `frame_count` frames without presenting them, with the camera following
+    /// `camera_path`, and reports their CPU and GPU times. The path is evaluated at
+    /// a `t` going from 0 to 1 over the run, so the same path gives comparable numbers
+    /// across runs and machines.
+    ///
+    /// Pipelines are compiled, and temporal accumulation warmed up, at the start of
+    /// the path before measuring. The sun is fixed, unless `world_renderer.time_of_day` is set.
+    pub fn benchmark(
+        self,
+        frame_count: u32,
+        mut camera_path: impl FnMut(f32) -> CameraMatrices,
+    ) -> anyhow::Result<BenchReport> {
+        // Enough for temporal accumulation to settle at the start of the path
+        const WARMUP_FRAMES: u32 = 32;
+
+        let SimpleMainLoop {
+            window,
+            mut world_renderer,
+            render_backend,
+            mut rg_renderer,
+            render_extent,
+            ..
+        } = self;
+
+        let output_extent = [
+            window.inner_size().width.max(1),
+            window.inner_size().height.max(1),
+        ];
+        let output_image = Arc::new(render_backend.device.create_image(
+            ImageDesc::new_2d(vk::Format::R16G16B16A16_SFLOAT, output_extent).usage(
+                vk::ImageUsageFlags::STORAGE
+                    | vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST,
+            ),
+            vec![],
+        )?);
+
+        let mut report = BenchReport {
+            frame_count,
+            gpu_frame_times: Vec::with_capacity(frame_count as usize),
+            cpu_frame_times: Vec::with_capacity(frame_count as usize),
+        };
+
+        for frame_idx in 0..WARMUP_FRAMES + frame_count {
+            let measured_idx = frame_idx.checked_sub(WARMUP_FRAMES);
+            let t = match measured_idx {
+                Some(idx) if frame_count > 1 => idx as f32 / (frame_count - 1) as f32,
+                _ => 0.0,
+            };
+
+            gpu_profiler::profiler().begin_frame();
+            let frame_start = Instant::now();
+
+            let frame_desc = WorldFrameDesc {
+                camera_matrices: camera_path(t),
+                render_extent,
+                sun_direction: glam::Vec3::new(-0.3, 1.0, 0.4).normalize(),
+            };
+
+            rg_renderer.prepare_frame(|rg| {
+                let main_img = world_renderer.prepare_render_graph(rg, &frame_desc);
+                let mut output = rg.get_swap_chain();
+                rg::imageops::blit_image(rg, &main_img, &mut output);
+            })?;
+
+            rg_renderer.render_to_image(
+                |dynamic_constants| {
+                    world_renderer.prepare_frame_constants(
+                        dynamic_constants,
+                        &frame_desc,
+                        1.0 / 60.0,
+                    )
+                },
+                ExternalImageTarget {
+                    image: output_image.clone(),
+                    initial_access: vk_sync::AccessType::Nothing,
+                    final_access: vk_sync::AccessType::TransferRead,
+                    wait_semaphore: None,
+                    signal_semaphore: None,
+                },
+            )?;
+            world_renderer.retire_frame();
+
+            gpu_profiler::profiler().end_frame();
+
+            if measured_idx.is_some() {
+                report.cpu_frame_times.push(frame_start.elapsed());
+
+                if let Some(gpu_frame_time) = rg_renderer
+                    .last_frame_stats()
+                    .and_then(|stats| stats.gpu_frame_time)
+                {
+                    report.gpu_frame_times.push(gpu_frame_time);
+                }
+            }
+        }
+
+        Ok(report)
     }
 
     pub fn run<'a, FrameFn>(self, mut frame_fn: FrameFn) -> anyhow::Result<()>
@@ -319,9 +493,11 @@ impl SimpleMainLoop {
             mut render_backend,
             mut rg_renderer,
             render_extent,
+            mut present_sharpening,
         } = self;
 
         let mut events = Vec::new();
+        let mut overlay: Option<ScreenOverlay> = None;
 
         let mut last_frame_instant = std::time::Instant::now();
         let mut last_error_text = None;
@@ -388,6 +564,21 @@ impl SimpleMainLoop {
 
             puffin::profile_scope!("MainEventsCleared");
 
+            // Minimized windows (notably on Windows) report a zero-sized client area. There is
+            // nothing to present to, and the swapchain extent would produce divisions by zero,
+            // so keep pumping events until the window comes back. Events received meanwhile
+            // are dropped, since they'd otherwise pile up without bound.
+            let window_size = window.inner_size();
+            if window_size.width == 0 || window_size.height == 0 {
+                events.clear();
+                gpu_profiler::profiler().end_frame();
+                std::thread::sleep(std::time::Duration::from_millis(10));
+
+                // Don't count the time spent minimized as a frame.
+                last_frame_instant = std::time::Instant::now();
+                continue;
+            }
+
             // Filter the frame time before passing it to the application and renderer.
             // Fluctuations in frame rendering times cause stutter in animations,
             // and time-dependent effects (such as motion blur).
@@ -422,9 +613,12 @@ impl SimpleMainLoop {
             let frame_desc = frame_fn(FrameContext {
                 dt_filtered,
                 render_extent,
+                output_extent: [window_size.width, window_size.height],
                 events: &events,
                 world_renderer: &mut world_renderer,
                 window: &window,
+                overlay: &mut overlay,
+                present_sharpening: &mut present_sharpening,
 
                 #[cfg(feature = "dear-imgui")]
                 imgui: Some(ImguiContext {
@@ -440,6 +634,9 @@ impl SimpleMainLoop {
 
             // Physical window extent in pixels
             let swapchain_extent = [window.inner_size().width, window.inner_size().height];
+            let swapchain_is_srgb = render_backend.swapchain.desc.is_srgb();
+            let swapchain_present_path = render_backend.swapchain.present_path;
+            let sharpening = present_sharpening.clamp(0.0, 1.0);
 
             let prepared_frame = {
                 puffin::profile_scope!("prepare_frame");
@@ -448,31 +645,70 @@ impl SimpleMainLoop {
                     let main_img = world_renderer.prepare_render_graph(rg, &frame_desc);
                     let ui_img = ui_renderer.prepare_render_graph(rg);
 
-                    let mut swap_chain = rg.get_swap_chain();
-                    rg::SimpleRenderPass::new_compute(
-                        rg.add_pass("final blit"),
-                        "/shaders/final_blit.hlsl",
-                    )
-                    .read(&main_img)
-                    .read(&ui_img)
-                    .write(&mut swap_chain)
-                    .constants((
-                        main_img.desc().extent_inv_extent_2d(),
-                        [
-                            swapchain_extent[0] as f32,
-                            swapchain_extent[1] as f32,
-                            1.0 / swapchain_extent[0] as f32,
-                            1.0 / swapchain_extent[1] as f32,
-                        ],
-                    ))
-                    .dispatch([swapchain_extent[0], swapchain_extent[1], 1]);
+                    let (overlay_img, overlay_rect) = match &overlay {
+                        Some(overlay) => (
+                            rg.import(
+                                overlay.image.clone(),
+                                vk_sync::AccessType::AnyShaderReadSampledImageOrUniformTexelBuffer,
+                            ),
+                            overlay.rect,
+                        ),
+                        None => {
+                            let mut blank_img =
+                                rg.create(ImageDesc::new_2d(vk::Format::R8G8B8A8_UNORM, [1, 1]));
+                            rg::imageops::clear_color(rg, &mut blank_img, [0.0f32; 4]);
+                            (blank_img, [0.0f32; 4])
+                        }
+                    };
+
+                    let mut swap_chain = rg.get_swap_chain_with_extent(swapchain_extent);
+
+                    let final_blit = |rg: &mut rg::RenderGraph, output: &mut rg::Handle<Image>| {
+                        rg::SimpleRenderPass::new_compute(
+                            rg.add_pass("final blit"),
+                            "/shaders/final_blit.hlsl",
+                        )
+                        .read(&main_img)
+                        .read(&ui_img)
+                        .read(&overlay_img)
+                        .write(output)
+                        .constants((
+                            main_img.desc().extent_inv_extent_2d(),
+                            [
+                                swapchain_extent[0] as f32,
+                                swapchain_extent[1] as f32,
+                                1.0 / swapchain_extent[0] as f32,
+                                1.0 / swapchain_extent[1] as f32,
+                            ],
+                            overlay_rect,
+                            swapchain_is_srgb as u32,
+                            sharpening,
+                        ))
+                        .dispatch([
+                            swapchain_extent[0],
+                            swapchain_extent[1],
+                            1,
+                        ]);
+                    };
+
+                    match swapchain_present_path {
+                        SwapchainPresentPath::ComputeWrite => final_blit(rg, &mut swap_chain),
+                        SwapchainPresentPath::Blit => {
+                            let mut blit_src = rg.create(ImageDesc::new_2d(
+                                vk::Format::R16G16B16A16_SFLOAT,
+                                swapchain_extent,
+                            ));
+                            final_blit(rg, &mut blit_src);
+                            rg::imageops::blit_image(rg, &blit_src, &mut swap_chain);
+                        }
+                    }
                 })
             };
 
             match prepared_frame {
                 Ok(()) => {
                     puffin::profile_scope!("draw_frame");
-                    rg_renderer.draw_frame(
+                    let drawn = rg_renderer.draw_frame(
                         |dynamic_constants| {
                             world_renderer.prepare_frame_constants(
                                 dynamic_constants,
@@ -482,8 +718,43 @@ impl SimpleMainLoop {
                         },
                         &mut render_backend.swapchain,
                     );
+
+                    // Skipped frames still had their main GPU work submitted,
+                    // so they're retired like presented ones.
+                    match drawn {
+                        Ok(()) => {
+                            last_error_text = None;
+                        }
+                        Err(err @ RenderError::OutputExtentMismatch { .. }) => {
+                            log::debug!("Skipping the frame: {}", err);
+                        }
+                        Err(err @ RenderError::SwapchainOutOfDate) => {
+                            log::debug!("Skipping the frame: {}", err);
+
+                            let window_size = window.inner_size();
+                            if let Err(err) = render_backend
+                                .recreate_swapchain([window_size.width, window_size.height])
+                            {
+                                log::error!("Rendering stopped: {:#}", err);
+                                return Err(err);
+                            }
+                        }
+                        Err(RenderError::SurfaceLost) => {
+                            log::warn!("The window surface has been lost; recreating it");
+                            if let Err(err) = render_backend.recreate_surface(&window) {
+                                log::error!("Rendering stopped: {:#}", err);
+                                return Err(err);
+                            }
+                        }
+                        // Recreating the device and every GPU resource in place is not supported;
+                        // bail out of the main loop with the error rather than aborting the process.
+                        Err(err) => {
+                            log::error!("Rendering stopped: {}", err);
+                            return Err(err.into());
+                        }
+                    }
+
                     world_renderer.retire_frame();
-                    last_error_text = None;
                 }
                 Err(e) => {
                     let error_text = Some(format!("{:?}", e));
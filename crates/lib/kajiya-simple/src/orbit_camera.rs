@@ -0,0 +1,96 @@
+use crate::input::MouseState;
+use glam::{Quat, Vec3};
+use kajiya::camera::{CameraLens, CameraMatrices, LookThroughCamera};
+use serde::{Deserialize, Serialize};
+
+// Keeps the camera from flipping over when looking straight up or down.
+const MAX_PITCH_DEGREES: f32 = 89.0;
+
+const LEFT_MOUSE_BUTTON: u32 = 1 << 0;
+const MIDDLE_MOUSE_BUTTON: u32 = 1 << 1;
+
+/// Camera circling around `focus_point`, controlled with the mouse:
+/// left-drag rotates, middle-drag pans, and the wheel zooms.
+///
+/// Angles are in degrees. At zero yaw and pitch, the camera looks down -Z.
+/// Positive pitch looks up at the focus point from below.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct OrbitCamera {
+    pub focus_point: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+
+    /// Degrees per pixel of mouse movement.
+    #[serde(skip, default = "default_rotation_speed")]
+    pub rotation_speed: f32,
+
+    /// Fraction of `distance` per pixel of mouse movement.
+    #[serde(skip, default = "default_pan_speed")]
+    pub pan_speed: f32,
+
+    /// Fraction of `distance` per wheel line.
+    #[serde(skip, default = "default_zoom_speed")]
+    pub zoom_speed: f32,
+}
+
+fn default_rotation_speed() -> f32 {
+    0.25
+}
+
+fn default_pan_speed() -> f32 {
+    0.0015
+}
+
+fn default_zoom_speed() -> f32 {
+    0.1
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            focus_point: Vec3::ZERO,
+            distance: 5.0,
+            yaw: 0.0,
+            pitch: -20.0,
+            rotation_speed: default_rotation_speed(),
+            pan_speed: default_pan_speed(),
+            zoom_speed: default_zoom_speed(),
+        }
+    }
+}
+
+impl OrbitCamera {
+    /// Applies the mouse input of the last frame. Mouse deltas are already per-frame,
+    /// so there's no need for the frame time.
+    pub fn update(&mut self, mouse: &MouseState) {
+        if mouse.buttons_held & LEFT_MOUSE_BUTTON != 0 {
+            self.yaw -= mouse.delta.x * self.rotation_speed;
+            self.pitch -= mouse.delta.y * self.rotation_speed;
+        }
+
+        if mouse.buttons_held & MIDDLE_MOUSE_BUTTON != 0 {
+            let pan = Vec3::new(-mouse.delta.x, mouse.delta.y, 0.0) * self.pan_speed;
+            self.focus_point += self.rotation() * pan * self.distance;
+        }
+
+        self.distance *= (-mouse.wheel_delta * self.zoom_speed).exp();
+        self.distance = self.distance.max(1e-3);
+        self.yaw = self.yaw.rem_euclid(360.0);
+        self.pitch = self.pitch.clamp(-MAX_PITCH_DEGREES, MAX_PITCH_DEGREES);
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_y(self.yaw.to_radians())
+            * Quat::from_rotation_x(self.pitch.to_radians())
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.focus_point + self.rotation() * Vec3::Z * self.distance
+    }
+
+    /// Matrices for `WorldFrameDesc::camera_matrices`.
+    pub fn camera_matrices(&self, lens: &CameraLens) -> CameraMatrices {
+        (self.position(), self.rotation()).through(lens)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::math::*;
-use rust_shaders_shared::camera::CameraMatrices;
+pub use rust_shaders_shared::camera::CameraMatrices;
 
 pub trait IntoCameraBodyMatrices {
     fn into_camera_body_matrices(self) -> CameraBodyMatrices;
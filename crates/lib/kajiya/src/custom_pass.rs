@@ -0,0 +1,49 @@
+use kajiya_backend::{ash::vk, vulkan::image::*};
+use kajiya_rg::{self as rg};
+
+use crate::{frame_desc::WorldFrameDesc, renderers::depth_write::DepthWriteRenderer};
+
+/// Scene resources handed to custom passes registered with `WorldRenderer::add_custom_pass`.
+pub struct CustomPassFrame<'a> {
+    pub frame_desc: &'a WorldFrameDesc,
+
+    /// Lit HDR color at the internal render resolution, in linear pre-exposed radiance.
+    /// Passes can read it, write to it, or replace the handle with a new image of the same
+    /// extent and format to have the rest of the frame use that instead.
+    pub color: &'a mut rg::Handle<Image>,
+
+    /// Reverse-Z depth at the internal render resolution. Passes producing their own
+    /// surfaces, e.g. by raymarching, can merge them in with `write_linear_depth`.
+    pub depth: &'a mut rg::Handle<Image>,
+
+    /// View-space geometry normals, `* 2 - 1` to decode.
+    pub geometric_normal: &'a rg::Handle<Image>,
+
+    /// Needed to bind mesh and material data, at descriptor set index 1.
+    pub bindless_descriptor_set: vk::DescriptorSet,
+
+    pub(crate) depth_write: &'a DepthWriteRenderer,
+}
+
+impl<'a> CustomPassFrame<'a> {
+    /// Merges view-space linear depth computed by a custom pass into `depth`, so that
+    /// the raster passes which follow, such as debug drawing, are occluded by those surfaces.
+    /// See `DepthWriteRenderer::write_linear_depth`.
+    pub fn write_linear_depth(
+        &mut self,
+        rg: &mut rg::RenderGraph,
+        linear_depth: &rg::Handle<Image>,
+    ) {
+        self.depth_write
+            .write_linear_depth(rg, linear_depth, self.depth);
+    }
+}
+
+/// Records a custom pass into the frame's render graph.
+///
+/// Runs every frame in the standard render mode, after lighting and before debug drawing,
+/// anti-aliasing and post-processing. Custom passes run in the order they were added,
+/// each seeing the `color` left by the previous one. They don't run in the reference
+/// path tracing mode, or while the renderer is paused.
+pub type CustomPassCallback =
+    Box<dyn FnMut(&mut rg::TemporalRenderGraph, CustomPassFrame) + 'static>;
@@ -1,4 +1,5 @@
 pub mod camera;
+pub mod custom_pass;
 pub mod default_world_renderer;
 pub mod frame_desc;
 pub mod image_cache;
@@ -8,6 +9,7 @@ pub mod lut_renderers;
 pub mod math;
 pub mod mmap;
 pub mod renderers;
+pub mod time_of_day;
 pub mod ui_renderer;
 pub mod world_render_passes;
 pub mod world_renderer;
@@ -19,4 +21,5 @@ mod buffer_builder;
 pub use kajiya_asset as asset;
 pub use kajiya_backend as backend;
 pub use kajiya_rg as rg;
+pub use rust_shaders_shared::coordinate_system;
 pub use rust_shaders_shared::render_overrides::*;
@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use glam::{Vec3, Vec4};
+use kajiya_backend::{
+    ash::vk,
+    dynamic_constants::MAX_DYNAMIC_CONSTANTS_STORAGE_BUFFER_BYTES,
+    vk_sync::AccessType,
+    vulkan::{image::*, shader::*},
+    Device,
+};
+use kajiya_rg::{self as rg};
+use rg::{IntoRenderPassPipelineBinding, RenderPassBinding};
+use rust_shaders_shared::{camera::CameraMatrices, coordinate_system::CoordinateSystem};
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct DebugVertex {
+    position: [f32; 4],
+    color: [f32; 4],
+}
+
+const MAX_DEBUG_VERTICES: usize =
+    MAX_DYNAMIC_CONSTANTS_STORAGE_BUFFER_BYTES / std::mem::size_of::<DebugVertex>();
+
+const SPHERE_SEGMENTS: usize = 32;
+
+/// Immediate-mode line drawing for debug visualization.
+///
+/// Primitives are accumulated over the frame, and drawn on top of the lit scene,
+/// depth-tested against the gbuffer depth. They're cleared after each frame.
+///
+/// Positions are in the convention set by `WorldRenderer::set_coordinate_system`.
+pub struct DebugDrawRenderer {
+    render_pass: Arc<RenderPass>,
+    line_vertices: Vec<DebugVertex>,
+    pub(crate) coordinate_system: CoordinateSystem,
+}
+
+impl DebugDrawRenderer {
+    pub fn new(device: &Device) -> Self {
+        let render_pass = create_render_pass(
+            device,
+            RenderPassDesc {
+                color_attachments: &[RenderPassAttachmentDesc::new(
+                    vk::Format::R16G16B16A16_SFLOAT,
+                )],
+                depth_attachment: Some(RenderPassAttachmentDesc::new(device.depth_format())),
+            },
+        );
+
+        Self {
+            render_pass,
+            line_vertices: Default::default(),
+            coordinate_system: CoordinateSystem::KAJIYA,
+        }
+    }
+
+    /// `color` is in linear HDR, and goes through exposure and tone mapping.
+    pub fn line(&mut self, a: Vec3, b: Vec3, color: Vec3) {
+        let color: [f32; 4] = color.extend(1.0).into();
+        let to_kajiya = self.coordinate_system.to_kajiya();
+        let (a, b) = (to_kajiya * a, to_kajiya * b);
+
+        self.line_vertices.push(DebugVertex {
+            position: a.extend(1.0).into(),
+            color,
+        });
+        self.line_vertices.push(DebugVertex {
+            position: b.extend(1.0).into(),
+            color,
+        });
+    }
+
+    /// Draws a wireframe sphere as three axis-aligned great circles.
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: Vec3) {
+        let circle_point = |axis: usize, i: usize| {
+            let angle = i as f32 / SPHERE_SEGMENTS as f32 * std::f32::consts::TAU;
+            let (s, c) = angle.sin_cos();
+            let p = match axis {
+                0 => Vec3::new(0.0, c, s),
+                1 => Vec3::new(c, 0.0, s),
+                _ => Vec3::new(c, s, 0.0),
+            };
+            center + p * radius
+        };
+
+        for axis in 0..3 {
+            for i in 0..SPHERE_SEGMENTS {
+                self.line(circle_point(axis, i), circle_point(axis, i + 1), color);
+            }
+        }
+    }
+
+    /// Draws the view frustum of `camera`, cut off at `far_distance` from the eye,
+    /// since the projection has no far plane.
+    pub fn frustum(&mut self, camera: &CameraMatrices, far_distance: f32, color: Vec3) {
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| {
+            // Reverse-Z: the near plane is at clip-space depth 1.
+            let near = camera.clip_to_view * Vec4::new(x, y, 1.0, 1.0);
+            let near = near.truncate() / near.w;
+            let far = near * (far_distance / -near.z);
+
+            (
+                camera.view_to_world.transform_point3(near),
+                camera.view_to_world.transform_point3(far),
+            )
+        });
+
+        for (i, &(near, far)) in corners.iter().enumerate() {
+            let (next_near, next_far) = corners[(i + 1) % 4];
+
+            self.line(near, next_near, color);
+            self.line(far, next_far, color);
+            self.line(near, far, color);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.line_vertices.clear();
+    }
+
+    pub fn render(
+        &mut self,
+        rg: &mut rg::RenderGraph,
+        output: &mut rg::Handle<Image>,
+        depth: &mut rg::Handle<Image>,
+    ) {
+        if self.line_vertices.is_empty() {
+            return;
+        }
+
+        let mut line_vertices = std::mem::take(&mut self.line_vertices);
+        if line_vertices.len() > MAX_DEBUG_VERTICES {
+            log::warn!(
+                "Too many debug draw vertices ({}); only drawing the first {}",
+                line_vertices.len(),
+                MAX_DEBUG_VERTICES
+            );
+            line_vertices.truncate(MAX_DEBUG_VERTICES & !1);
+        }
+
+        let mut pass = rg.add_pass("debug draw");
+
+        let pipeline = pass.register_raster_pipeline(
+            &[
+                PipelineShaderDesc::builder(ShaderPipelineStage::Vertex)
+                    .hlsl_source("/shaders/debug_draw_vs.hlsl")
+                    .build()
+                    .unwrap(),
+                PipelineShaderDesc::builder(ShaderPipelineStage::Pixel)
+                    .hlsl_source("/shaders/debug_draw_ps.hlsl")
+                    .build()
+                    .unwrap(),
+            ],
+            RasterPipelineDesc::builder()
+                .render_pass(self.render_pass.clone())
+                .depth_write(false)
+                .topology(vk::PrimitiveTopology::LINE_LIST),
+        );
+
+        let depth_ref = pass.raster(depth, AccessType::DepthAttachmentWriteStencilReadOnly);
+        let output_ref = pass.raster(output, AccessType::ColorAttachmentWrite);
+
+        let render_pass = self.render_pass.clone();
+
+        pass.render(move |api| {
+            let [width, height, _] = output_ref.desc().extent;
+
+            let vertices_offset = api
+                .dynamic_constants()
+                .push_from_iter(line_vertices.iter().copied());
+
+            api.begin_render_pass(
+                &render_pass,
+                [width, height],
+                &[(output_ref, &ImageViewDesc::default())],
+                Some((
+                    depth_ref,
+                    &ImageViewDesc::builder()
+                        .aspect_mask(vk::ImageAspectFlags::DEPTH)
+                        .build()
+                        .unwrap(),
+                )),
+            )?;
+
+            api.set_default_view_and_scissor([width, height]);
+
+            let pipeline = api.bind_raster_pipeline(pipeline.into_binding().descriptor_set(
+                0,
+                &[RenderPassBinding::DynamicConstantsStorageBuffer(
+                    vertices_offset,
+                )],
+            ))?;
+
+            pipeline.draw(line_vertices.len() as u32, 1);
+
+            api.end_render_pass();
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use kajiya_backend::{
+    ash::vk,
+    vk_sync::AccessType,
+    vulkan::{image::*, shader::*},
+    Device,
+};
+use kajiya_rg::{self as rg, BindRgRef, IntoRenderPassPipelineBinding};
+
+/// Writes depth computed outside of raster passes into a depth attachment, so that
+/// subsequent raster passes can depth-test against it. Used by
+/// `CustomPassFrame::write_linear_depth`.
+///
+/// Depth formats generally can't be bound as storage images, hence this goes through
+/// a full-screen draw which outputs `SV_Depth`.
+pub struct DepthWriteRenderer {
+    render_pass: Arc<RenderPass>,
+}
+
+impl DepthWriteRenderer {
+    pub fn new(device: &Device) -> Self {
+        let render_pass = create_render_pass(
+            device,
+            RenderPassDesc {
+                color_attachments: &[],
+                depth_attachment: Some(RenderPassAttachmentDesc::new(device.depth_format())),
+            },
+        );
+
+        Self { render_pass }
+    }
+
+    /// `linear_depth` holds positive view-space distance along the camera axis, with zero
+    /// where there's no geometry (as produced by `GbufferDepth::linear_depth`).
+    ///
+    /// It's converted to the reverse-Z convention of `depth`, and merged with its contents
+    /// via the regular depth test, so the nearer surface wins.
+    pub fn write_linear_depth(
+        &self,
+        rg: &mut rg::RenderGraph,
+        linear_depth: &rg::Handle<Image>,
+        depth: &mut rg::Handle<Image>,
+    ) {
+        let mut pass = rg.add_pass("write linear depth");
+
+        let pipeline = pass.register_raster_pipeline(
+            &[
+                PipelineShaderDesc::builder(ShaderPipelineStage::Vertex)
+                    .hlsl_source("/shaders/write_depth_vs.hlsl")
+                    .build()
+                    .unwrap(),
+                PipelineShaderDesc::builder(ShaderPipelineStage::Pixel)
+                    .hlsl_source("/shaders/write_depth_ps.hlsl")
+                    .build()
+                    .unwrap(),
+            ],
+            RasterPipelineDesc::builder()
+                .render_pass(self.render_pass.clone())
+                .depth_write(true),
+        );
+
+        let linear_depth_ref = pass.read(
+            linear_depth,
+            AccessType::FragmentShaderReadSampledImageOrUniformTexelBuffer,
+        );
+        let depth_ref = pass.raster(depth, AccessType::DepthAttachmentWriteStencilReadOnly);
+
+        let render_pass = self.render_pass.clone();
+
+        pass.render(move |api| {
+            let [width, height, _] = depth_ref.desc().extent;
+
+            api.begin_render_pass(
+                &render_pass,
+                [width, height],
+                &[],
+                Some((
+                    depth_ref,
+                    &ImageViewDesc::builder()
+                        .aspect_mask(vk::ImageAspectFlags::DEPTH)
+                        .build()
+                        .unwrap(),
+                )),
+            )?;
+
+            api.set_default_view_and_scissor([width, height]);
+
+            let pipeline = api.bind_raster_pipeline(
+                pipeline
+                    .into_binding()
+                    .descriptor_set(0, &[linear_depth_ref.bind()]),
+            )?;
+
+            // Full-screen triangle
+            pipeline.draw(3, 1);
+
+            api.end_render_pass();
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,49 @@
+use kajiya_backend::{ash::vk, vulkan::image::*};
+use kajiya_rg::{self as rg, SimpleRenderPass};
+
+/// Resolves a depth(-stencil) image into an `R32_SFLOAT` color image holding positive
+/// view-space depth, or zero where there's no geometry. Only the depth aspect is read,
+/// so combined formats such as `D24_UNORM_S8_UINT` work, and the output can be sampled
+/// like any other texture.
+pub fn extract_linear_depth(
+    rg: &mut rg::RenderGraph,
+    depth: &rg::Handle<Image>,
+) -> rg::Handle<Image> {
+    let mut output_tex = rg.create(
+        depth
+            .desc()
+            .usage(vk::ImageUsageFlags::empty())
+            .format(vk::Format::R32_SFLOAT),
+    );
+    SimpleRenderPass::new_compute(
+        rg.add_pass("extract linear depth"),
+        "/shaders/extract_linear_depth.hlsl",
+    )
+    .read_aspect(depth, vk::ImageAspectFlags::DEPTH)
+    .write(&mut output_tex)
+    .constants(output_tex.desc().extent_inv_extent_2d())
+    .dispatch(output_tex.desc().extent);
+    output_tex
+}
+
+/// Like `extract_linear_depth`, but outputs world-space positions in `R32G32B32A32_SFLOAT`.
+pub fn extract_world_position(
+    rg: &mut rg::RenderGraph,
+    depth: &rg::Handle<Image>,
+) -> rg::Handle<Image> {
+    let mut output_tex = rg.create(
+        depth
+            .desc()
+            .usage(vk::ImageUsageFlags::empty())
+            .format(vk::Format::R32G32B32A32_SFLOAT),
+    );
+    SimpleRenderPass::new_compute(
+        rg.add_pass("extract world position"),
+        "/shaders/extract_world_position.hlsl",
+    )
+    .read_aspect(depth, vk::ImageAspectFlags::DEPTH)
+    .write(&mut output_tex)
+    .constants(output_tex.desc().extent_inv_extent_2d())
+    .dispatch(output_tex.desc().extent);
+    output_tex
+}
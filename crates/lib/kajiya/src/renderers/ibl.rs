@@ -22,6 +22,8 @@ impl IblRenderer {
         self.texture = None;
     }
 
+    /// Loads an `.exr` or `.hdr` environment map. Both formats store linear radiance,
+    /// which is used as-is, in the same space as the procedural sky it replaces.
     pub fn load_image(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
         let img = load_image(path.as_ref())?;
 
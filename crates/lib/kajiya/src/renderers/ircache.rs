@@ -106,7 +106,7 @@ impl IrcacheRenderer {
                     // view-space geometry normal; * 2 - 1 to decode
                     RenderPassAttachmentDesc::new(vk::Format::R32G32B32A32_SFLOAT),
                 ],
-                depth_attachment: Some(RenderPassAttachmentDesc::new(vk::Format::D32_SFLOAT)),
+                depth_attachment: Some(RenderPassAttachmentDesc::new(device.depth_format())),
             },
         );
 
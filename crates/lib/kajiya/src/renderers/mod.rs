@@ -3,8 +3,11 @@ use std::cell::{Ref, RefCell};
 use kajiya_backend::Image;
 use kajiya_rg::{self as rg, GetOrCreateTemporal};
 
+pub mod debug_draw;
 pub mod deferred;
+pub mod depth_write;
 pub mod dof;
+pub mod gbuffer_extract;
 pub mod half_res;
 pub mod ibl;
 pub mod ircache;
@@ -13,6 +16,7 @@ pub mod motion_blur;
 pub mod post;
 pub mod prefix_scan;
 pub mod raster_meshes;
+pub mod reduce;
 pub mod reference;
 pub mod reprojection;
 pub mod rtdgi;
@@ -34,6 +38,8 @@ pub struct GbufferDepth {
     pub depth: rg::Handle<Image>,
     half_view_normal: RefCell<Option<rg::Handle<Image>>>,
     half_depth: RefCell<Option<rg::Handle<Image>>>,
+    linear_depth: RefCell<Option<rg::Handle<Image>>>,
+    world_position: RefCell<Option<rg::Handle<Image>>>,
 }
 
 impl GbufferDepth {
@@ -48,6 +54,8 @@ impl GbufferDepth {
             depth,
             half_view_normal: Default::default(),
             half_depth: Default::default(),
+            linear_depth: Default::default(),
+            world_position: Default::default(),
         }
     }
 
@@ -68,6 +76,26 @@ impl GbufferDepth {
 
         Ref::map(self.half_depth.borrow(), |res| res.as_ref().unwrap())
     }
+
+    /// View-space linear depth, reconstructed from `depth` on first use.
+    pub fn linear_depth(&self, rg: &mut rg::RenderGraph) -> Ref<rg::Handle<Image>> {
+        if self.linear_depth.borrow().is_none() {
+            *self.linear_depth.borrow_mut() =
+                Some(gbuffer_extract::extract_linear_depth(rg, &self.depth));
+        }
+
+        Ref::map(self.linear_depth.borrow(), |res| res.as_ref().unwrap())
+    }
+
+    /// World-space position, reconstructed from `depth` on first use.
+    pub fn world_position(&self, rg: &mut rg::RenderGraph) -> Ref<rg::Handle<Image>> {
+        if self.world_position.borrow().is_none() {
+            *self.world_position.borrow_mut() =
+                Some(gbuffer_extract::extract_world_position(rg, &self.depth));
+        }
+
+        Ref::map(self.world_position.borrow(), |res| res.as_ref().unwrap())
+    }
 }
 
 pub struct PingPongTemporalResource {
@@ -4,7 +4,7 @@ use kajiya_backend::{ash::vk, vk_sync::AccessType, vulkan::image::*, BackendErro
 use kajiya_rg::{self as rg};
 use rg::{Buffer, BufferDesc, RenderGraph, SimpleRenderPass};
 
-use crate::world_renderer::HistogramClipping;
+use crate::world_renderer::{HistogramClipping, TonemapOperator};
 
 pub fn blur_pyramid(rg: &mut RenderGraph, input: &rg::Handle<Image>) -> rg::Handle<Image> {
     let skip_n_bottom_mips = 1;
@@ -238,6 +238,7 @@ impl PostProcessRenderer {
         bindless_descriptor_set: vk::DescriptorSet,
         post_exposure_mult: f32,
         contrast: f32,
+        tonemap_operator: TonemapOperator,
         exposure_histogram_clipping: HistogramClipping,
     ) -> rg::Handle<Image> {
         self.read_back_histogram(exposure_histogram_clipping);
@@ -264,6 +265,7 @@ impl PostProcessRenderer {
                 output.desc().extent_inv_extent_2d(),
                 post_exposure_mult,
                 contrast,
+                tonemap_operator as u32,
             ))
             .dispatch(output.desc().extent);
 
@@ -0,0 +1,140 @@
+use std::mem::size_of;
+
+use kajiya_backend::{
+    ash::vk,
+    vulkan::{
+        buffer::{Buffer, BufferDesc},
+        image::*,
+    },
+};
+use kajiya_rg::{self as rg, SimpleRenderPass};
+
+#[derive(Clone, Copy)]
+pub enum ReduceOp {
+    Min,
+    Max,
+    Sum,
+    /// Counts of values falling into `bin_count` equal bins spanning `min_value..max_value`.
+    /// Out-of-range values are clamped into the first and last bins.
+    Histogram {
+        bin_count: u32,
+        min_value: f32,
+        max_value: f32,
+    },
+}
+
+impl ReduceOp {
+    // Must match `reduce_common.hlsl`
+    fn shader_op(self) -> u32 {
+        match self {
+            ReduceOp::Min => 0,
+            ReduceOp::Max => 1,
+            ReduceOp::Sum => 2,
+            ReduceOp::Histogram { .. } => unreachable!(),
+        }
+    }
+}
+
+/// Reduces the first channel of `input`.
+///
+/// For `Min`, `Max`, and `Sum`, the result is a single `f32`. For `Histogram`,
+/// it's `bin_count` `u32` counters.
+///
+/// Uses wave intrinsics if the device supports subgroup arithmetic in compute shaders.
+pub fn reduce_image(
+    rg: &mut rg::TemporalRenderGraph,
+    input: &rg::Handle<Image>,
+    op: ReduceOp,
+) -> rg::Handle<Buffer> {
+    let input_extent = input.desc().extent;
+
+    match op {
+        ReduceOp::Histogram {
+            bin_count,
+            min_value,
+            max_value,
+        } => {
+            let mut output_buf = rg.create(BufferDesc::new_gpu_only(
+                size_of::<u32>() * bin_count as usize,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+            ));
+
+            SimpleRenderPass::new_compute(
+                rg.add_pass("_clear reduce histogram"),
+                "/shaders/reduce/histogram_clear.hlsl",
+            )
+            .write(&mut output_buf)
+            .constants(bin_count)
+            .dispatch([bin_count, 1, 1]);
+
+            SimpleRenderPass::new_compute(
+                rg.add_pass("reduce histogram"),
+                "/shaders/reduce/histogram.hlsl",
+            )
+            .read(input)
+            .write(&mut output_buf)
+            .constants((
+                input_extent[0],
+                input_extent[1],
+                bin_count,
+                min_value,
+                max_value,
+            ))
+            .dispatch(input_extent);
+
+            output_buf
+        }
+        ReduceOp::Min | ReduceOp::Max | ReduceOp::Sum => {
+            let use_wave_ops = rg
+                .device()
+                .physical_device()
+                .subgroup_properties
+                .supports_compute_arithmetic();
+
+            let (reduce_image_shader, reduce_partials_shader) = if use_wave_ops {
+                (
+                    "/shaders/reduce/reduce_image_wave.hlsl",
+                    "/shaders/reduce/reduce_partials_wave.hlsl",
+                )
+            } else {
+                (
+                    "/shaders/reduce/reduce_image.hlsl",
+                    "/shaders/reduce/reduce_partials.hlsl",
+                )
+            };
+
+            // One partial result per 8x8 tile, folded by a single group in the second pass.
+            let group_count = input.desc().div_up_extent([8, 8, 1]).extent;
+            let partial_count = group_count[0] * group_count[1];
+
+            let mut partials_buf = rg.create(BufferDesc::new_gpu_only(
+                size_of::<f32>() * partial_count as usize,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+            ));
+
+            SimpleRenderPass::new_compute(rg.add_pass("reduce image"), reduce_image_shader)
+                .read(input)
+                .write(&mut partials_buf)
+                .constants((
+                    input_extent[0],
+                    input_extent[1],
+                    op.shader_op(),
+                    group_count[0],
+                ))
+                .dispatch(input_extent);
+
+            let mut output_buf = rg.create(BufferDesc::new_gpu_only(
+                size_of::<f32>(),
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+            ));
+
+            SimpleRenderPass::new_compute(rg.add_pass("_reduce partials"), reduce_partials_shader)
+                .read(&partials_buf)
+                .write(&mut output_buf)
+                .constants((partial_count, op.shader_op()))
+                .dispatch([64, 1, 1]);
+
+            output_buf
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use glam::Vec3;
+
+/// Position of the sun derived from the local solar time, latitude, and season.
+///
+/// Set on `WorldRenderer::time_of_day` to drive the sky, sun lighting, and shadows
+/// from one place, instead of `WorldFrameDesc::sun_direction`.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeOfDay {
+    /// Local solar time in hours; 12 is noon.
+    pub hours: f32,
+
+    /// Latitude of the observer in degrees, positive in the northern hemisphere.
+    pub latitude_degrees: f32,
+
+    /// Declination of the sun in degrees. Zero at the equinoxes, and ±23.44 at the solstices.
+    pub declination_degrees: f32,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            hours: 12.0,
+            latitude_degrees: 45.0,
+            declination_degrees: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SunState {
+    /// Direction _towards_ the sun. +Y is up, +X is east, and -Z is north.
+    pub direction: Vec3,
+
+    /// Multiplies the sun and sky radiance. Reddens slightly at low elevations, on top of
+    /// the atmosphere's own scattering, and fades out through twilight once the sun sets.
+    pub color_multiplier: Vec3,
+}
+
+// The sun fades out between these elevations, roughly matching civil twilight.
+const TWILIGHT_END_DEGREES: f32 = -6.0;
+const TWILIGHT_START_DEGREES: f32 = 2.0;
+
+// Elevation above which the twilight tint is gone.
+const GOLDEN_HOUR_END_DEGREES: f32 = 15.0;
+const TWILIGHT_TINT: Vec3 = glam::const_vec3!([1.0, 0.85, 0.7]);
+
+impl TimeOfDay {
+    pub fn sun(&self) -> SunState {
+        let hour_angle = ((self.hours - 12.0) * 15.0).to_radians();
+        let latitude = self.latitude_degrees.to_radians();
+        let declination = self.declination_degrees.to_radians();
+
+        // Horizontal coordinates of the sun from its hour angle and declination.
+        let up = latitude.sin() * declination.sin()
+            + latitude.cos() * declination.cos() * hour_angle.cos();
+        let east = -declination.cos() * hour_angle.sin();
+        let north = latitude.cos() * declination.sin()
+            - latitude.sin() * declination.cos() * hour_angle.cos();
+
+        let direction = Vec3::new(east, up, -north).normalize();
+        let elevation_degrees = up.clamp(-1.0, 1.0).asin().to_degrees();
+
+        let daylight = smoothstep(
+            TWILIGHT_END_DEGREES,
+            TWILIGHT_START_DEGREES,
+            elevation_degrees,
+        );
+        let tint = TWILIGHT_TINT.lerp(
+            Vec3::ONE,
+            smoothstep(0.0, GOLDEN_HOUR_END_DEGREES, elevation_degrees),
+        );
+
+        SunState {
+            direction,
+            color_multiplier: tint * daylight,
+        }
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -7,6 +7,18 @@ use kajiya_rg::{self as rg};
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 
+/// A pre-rendered image alpha-blended over the final frame, underneath the UI.
+#[derive(Clone)]
+pub struct ScreenOverlay {
+    /// Must have `SAMPLED` usage. Colors are expected to be sRGB-encoded (e.g. `R8G8B8A8_UNORM`),
+    /// with straight (non-premultiplied) alpha.
+    pub image: Arc<Image>,
+
+    /// `[x, y, width, height]` of the overlay, in fractions of the output size,
+    /// with the origin at the top left.
+    pub rect: [f32; 4],
+}
+
 #[derive(Default)]
 pub struct UiRenderer {
     pub ui_frame: Option<(UiRenderCallback, Arc<Image>)>,
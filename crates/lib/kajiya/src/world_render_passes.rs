@@ -1,4 +1,5 @@
 use crate::{
+    custom_pass::CustomPassFrame,
     frame_desc::WorldFrameDesc,
     renderers::{
         deferred::light_gbuffer, motion_blur::motion_blur, raster_meshes::*,
@@ -39,7 +40,7 @@ impl WorldRenderer {
 
         let convolved_sky_cube = crate::renderers::sky::convolve_cube(rg, &sky_cube);
 
-        let (gbuffer_depth, velocity_img) = {
+        let (mut gbuffer_depth, velocity_img) = {
             let mut gbuffer_depth = {
                 let normal = rg.create(ImageDesc::new_2d(
                     vk::Format::A2R10G10B10_UNORM_PACK32,
@@ -51,19 +52,18 @@ impl WorldRenderer {
                     frame_desc.render_extent,
                 ));
 
-                let mut depth_img = rg.create(ImageDesc::new_2d(
-                    vk::Format::D32_SFLOAT,
-                    frame_desc.render_extent,
-                ));
+                let depth_format = rg.device().depth_format();
+                let mut depth_img =
+                    rg.create(ImageDesc::new_2d(depth_format, frame_desc.render_extent));
                 rg::imageops::clear_depth(rg, &mut depth_img);
 
                 GbufferDepth::new(normal, gbuffer, depth_img)
             };
 
-            let mut velocity_img = rg.create(ImageDesc::new_2d(
-                vk::Format::R16G16B16A16_SFLOAT,
-                frame_desc.render_extent,
-            ));
+            // Must match the velocity attachment of `raster_simple_render_pass`
+            let velocity_format = rg.device().hdr_color_attachment_format();
+            let mut velocity_img =
+                rg.create(ImageDesc::new_2d(velocity_format, frame_desc.render_extent));
 
             raster_meshes(
                 rg,
@@ -233,6 +233,23 @@ impl WorldRenderer {
             self.debug_show_wrc,
         );
 
+        for custom_pass in &mut self.custom_passes {
+            custom_pass(
+                rg,
+                CustomPassFrame {
+                    frame_desc,
+                    color: &mut debug_out_tex,
+                    depth: &mut gbuffer_depth.depth,
+                    geometric_normal: &gbuffer_depth.geometric_normal,
+                    bindless_descriptor_set: self.bindless_descriptor_set,
+                    depth_write: &self.depth_write,
+                },
+            );
+        }
+
+        self.debug_draw
+            .render(rg, &mut debug_out_tex, &mut gbuffer_depth.depth);
+
         #[allow(unused_mut)]
         let mut anti_aliased = None;
 
@@ -262,8 +279,11 @@ impl WorldRenderer {
                 .this_frame_out
         });
 
-        let mut final_post_input =
-            motion_blur(rg, &anti_aliased, &gbuffer_depth.depth, &reprojection_map);
+        let mut final_post_input = if self.use_motion_blur {
+            motion_blur(rg, &anti_aliased, &gbuffer_depth.depth, &reprojection_map)
+        } else {
+            anti_aliased
+        };
 
         if let Some(tlas) = tlas.as_ref() {
             if matches!(self.debug_mode, RenderDebugMode::WorldRadianceCache) {
@@ -285,6 +305,7 @@ impl WorldRenderer {
             self.bindless_descriptor_set,
             self.exposure_state().post_mult,
             self.contrast,
+            self.tonemap_operator,
             self.dynamic_exposure.histogram_clipping,
         );
 
@@ -325,6 +346,7 @@ impl WorldRenderer {
             self.bindless_descriptor_set,
             self.exposure_state().post_mult,
             self.contrast,
+            self.tonemap_operator,
             self.dynamic_exposure.histogram_clipping,
         )
     }
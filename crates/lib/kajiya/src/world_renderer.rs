@@ -4,13 +4,16 @@ use crate::{
         BINDLESS_TEXURES_BINDING_INDEX,
     },
     buffer_builder::BufferBuilder,
+    custom_pass::CustomPassCallback,
     frame_desc::WorldFrameDesc,
     image_lut::{ComputeImageLut, ImageLut},
     renderers::{
-        ibl::IblRenderer, ircache::IrcacheRenderer, lighting::LightingRenderer,
-        post::PostProcessRenderer, raster_meshes::*, rtdgi::RtdgiRenderer, rtr::*,
-        shadow_denoise::ShadowDenoiseRenderer, ssgi::*, taa::TaaRenderer,
+        debug_draw::DebugDrawRenderer, depth_write::DepthWriteRenderer, ibl::IblRenderer,
+        ircache::IrcacheRenderer, lighting::LightingRenderer, post::PostProcessRenderer,
+        raster_meshes::*, rtdgi::RtdgiRenderer, rtr::*, shadow_denoise::ShadowDenoiseRenderer,
+        ssgi::*, taa::TaaRenderer,
     },
+    time_of_day::TimeOfDay,
 };
 use glam::{Affine3A, Vec2, Vec3};
 use kajiya_asset::mesh::{AssetRef, GpuImage, MeshMaterialFlags, PackedTriMesh, PackedVertex};
@@ -21,13 +24,14 @@ use kajiya_backend::{
     vulkan::{self, device, image::*, ray_tracing::*, shader::*, RenderBackend},
     BackendError,
 };
-use kajiya_rg::{self as rg};
+use kajiya_rg::{self as rg, GetOrCreateTemporal};
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 use parking_lot::Mutex;
 use rg::renderer::FrameConstantsLayout;
 use rust_shaders_shared::{
     camera::CameraMatrices,
+    coordinate_system::CoordinateSystem,
     frame_constants::{FrameConstants, IrcacheCascadeConstants, IRCACHE_CASCADE_COUNT},
     render_overrides::RenderOverrides,
     view_constants::ViewConstants,
@@ -172,13 +176,23 @@ pub struct WorldRenderer {
     bindless_texture_sizes: Buffer,
 
     image_luts: Vec<ImageLut>,
+    pub(super) custom_passes: Vec<CustomPassCallback>,
     frame_idx: u32,
     prev_camera_matrices: Option<CameraMatrices>,
+    paused: bool,
+    paused_frame: PausedFrame,
+    render_extent: [u32; 2],
+    coordinate_system: CoordinateSystem,
     pub(crate) temporal_upscale_extent: [u32; 2],
 
     supersample_offsets: Vec<Vec2>,
 
     pub rg_debug_hook: Option<rg::GraphDebugHook>,
+
+    /// Cameras of additional views (e.g. shadow or probe views) rendered within the frame's
+    /// graph. Passes select them with `PassBuilder::set_view`, where view `n` uses element `n - 1`.
+    pub additional_views: Vec<CameraMatrices>,
+
     pub render_mode: RenderMode,
     pub reset_reference_accumulation: bool,
 
@@ -191,6 +205,8 @@ pub struct WorldRenderer {
     pub taa: TaaRenderer,
     pub shadow_denoise: ShadowDenoiseRenderer,
     pub ibl: IblRenderer,
+    pub debug_draw: DebugDrawRenderer,
+    pub depth_write: DepthWriteRenderer,
 
     #[cfg(feature = "dlss")]
     pub dlss: DlssRenderer,
@@ -200,26 +216,54 @@ pub struct WorldRenderer {
     pub debug_mode: RenderDebugMode,
     pub debug_shading_mode: usize,
     pub debug_show_wrc: bool,
+    pub use_motion_blur: bool,
     pub ev_shift: f32,
     pub dynamic_exposure: DynamicExposureState,
     pub contrast: f32,
+    pub tonemap_operator: TonemapOperator,
 
     pub sun_size_multiplier: f32,
     pub sun_color_multiplier: Vec3,
     pub sky_ambient: Vec3,
 
+    /// If set, overrides `WorldFrameDesc::sun_direction`, and scales `sun_color_multiplier`
+    /// for twilight and night.
+    pub time_of_day: Option<TimeOfDay>,
+
     pub render_overrides: RenderOverrides,
 
     // One for each render mode
     pub(crate) exposure_state: [ExposureState; 2],
 }
 
+#[derive(Clone, Copy)]
+enum PausedFrame {
+    None,
+    Capturing(ImageDesc),
+    Captured(ImageDesc),
+}
+
 #[derive(Default, Clone, Copy)]
 pub struct HistogramClipping {
     pub low: f32,
     pub high: f32,
 }
 
+/// Curve mapping exposed HDR color to the displayable range in the post combine pass.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(u32)]
+pub enum TonemapOperator {
+    /// Perceptually neutral display transform, with hue-preserving highlight desaturation.
+    #[default]
+    DisplayTransform = 0,
+    /// Per-channel `x / (1 + x)`.
+    Reinhard = 1,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces = 2,
+    /// Clamps to the displayable range.
+    None = 3,
+}
+
 #[derive(Default)]
 pub struct DynamicExposureState {
     pub enabled: bool,
@@ -291,35 +335,63 @@ pub enum RenderMode {
     Reference = 1,
 }
 
+/// Bundles of quality settings for `WorldRenderer::apply_quality_preset`.
+/// `Medium` matches the renderer's defaults.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum QualityPreset {
+    Low,
+    Medium,
+    High,
+    Ultra,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct BindlessImageHandle(pub u32);
 
 fn load_gpu_image_asset(
     device: Arc<kajiya_backend::Device>,
     asset: AssetRef<GpuImage::Flat>,
-) -> Arc<Image> {
+) -> anyhow::Result<Arc<Image>> {
     let asset = crate::mmap::mmapped_asset::<GpuImage::Flat, _>(&format!(
         "/cache/{:8.8x}.image",
         asset.identity()
-    ))
-    .unwrap();
+    ))?;
+
+    let extent = [asset.extent[0], asset.extent[1]];
+    let mut format = asset.format;
+    let mut mips: Vec<&[u8]> = asset.mips.iter().map(|mip| mip.as_slice()).collect();
+
+    // Compressed assets are uploaded as-is where possible; otherwise decode them.
+    let decoded_mips;
+    if !device.supports_sampled_format(format) {
+        log::warn!(
+            "{:?} images can't be sampled on this device; decoding to RGBA8",
+            format
+        );
 
-    let desc = ImageDesc::new_2d(asset.format, [asset.extent[0], asset.extent[1]])
+        let (decoded_format, decoded) =
+            kajiya_asset::bc_decode::decode_to_rgba8(format, extent, &mips)?;
+
+        format = decoded_format;
+        decoded_mips = decoded;
+        mips = decoded_mips.iter().map(|mip| mip.as_slice()).collect();
+    }
+
+    let desc = ImageDesc::new_2d(format, extent)
         .usage(vk::ImageUsageFlags::SAMPLED)
-        .mip_levels(asset.mips.len() as _);
+        .mip_levels(mips.len() as _);
 
-    let initial_data = asset
-        .mips
+    let initial_data = mips
         .iter()
         .enumerate()
         .map(|(mip_level, mip)| ImageSubResourceData {
-            data: mip.as_slice(),
+            data: mip,
             row_pitch: ((desc.extent[0] as usize) >> mip_level).max(1) * 4,
             slice_pitch: 0,
         })
         .collect::<Vec<_>>();
 
-    Arc::new(device.create_image(desc, initial_data).unwrap())
+    Ok(Arc::new(device.create_image(desc, initial_data)?))
 }
 
 #[derive(Default)]
@@ -341,7 +413,7 @@ impl AddMeshOptions {
 impl WorldRenderer {
     pub(crate) fn new_empty(
         // Internal render resolution, before any upsampling
-        #[allow(unused_variables)] render_extent: [u32; 2],
+        render_extent: [u32; 2],
         temporal_upscale_extent: [u32; 2],
         backend: &RenderBackend,
     ) -> Result<Self, BackendError> {
@@ -355,9 +427,12 @@ impl WorldRenderer {
                     // gbuffer
                     RenderPassAttachmentDesc::new(vk::Format::R32G32B32A32_SFLOAT).garbage_input(),
                     // velocity
-                    RenderPassAttachmentDesc::new(vk::Format::R16G16B16A16_SFLOAT).garbage_input(),
+                    RenderPassAttachmentDesc::new(backend.device.hdr_color_attachment_format())
+                        .garbage_input(),
                 ],
-                depth_attachment: Some(RenderPassAttachmentDesc::new(vk::Format::D32_SFLOAT)),
+                depth_attachment: Some(RenderPassAttachmentDesc::new(
+                    backend.device.depth_format(),
+                )),
             },
         );
 
@@ -464,15 +539,20 @@ impl WorldRenderer {
             bindless_descriptor_set,
             bindless_images: Default::default(),
             image_luts: Default::default(),
+            custom_passes: Default::default(),
 
             next_bindless_image_id: 0,
             next_instance_handle: 0,
             bindless_texture_sizes,
 
             rg_debug_hook: None,
+            additional_views: Vec::new(),
             render_mode: RenderMode::Standard,
             frame_idx: 0u32,
             prev_camera_matrices: None,
+            paused: false,
+            paused_frame: PausedFrame::None,
+            coordinate_system: CoordinateSystem::KAJIYA,
 
             supersample_offsets,
 
@@ -485,12 +565,15 @@ impl WorldRenderer {
             taa: TaaRenderer::new(),
             shadow_denoise: ShadowDenoiseRenderer::default(),
             ibl: IblRenderer::default(),
+            debug_draw: DebugDrawRenderer::new(backend.device.as_ref()),
+            depth_write: DepthWriteRenderer::new(backend.device.as_ref()),
 
             #[cfg(feature = "dlss")]
             dlss,
             #[cfg(feature = "dlss")]
             use_dlss: true,
 
+            render_extent,
             temporal_upscale_extent,
 
             debug_mode: RenderDebugMode::None,
@@ -501,12 +584,15 @@ impl WorldRenderer {
                 4
             },
             debug_show_wrc: false,
+            use_motion_blur: true,
             ev_shift: 0.0,
             dynamic_exposure: Default::default(),
             contrast: 1.0,
+            tonemap_operator: Default::default(),
 
             sun_size_multiplier: 1.0, // Sun as seen from Earth
             sun_color_multiplier: Vec3::ONE,
+            time_of_day: None,
             sky_ambient: Vec3::ZERO,
 
             render_overrides: Default::default(),
@@ -564,6 +650,12 @@ impl WorldRenderer {
         handle
     }
 
+    /// Adds a pass recorded into every frame's render graph, with access to the scene's
+    /// color and depth. See `CustomPassCallback` for where it runs relative to built-in passes.
+    pub fn add_custom_pass(&mut self, pass: CustomPassCallback) {
+        self.custom_passes.push(pass);
+    }
+
     pub fn add_image_lut(&mut self, computer: impl ComputeImageLut + 'static, id: usize) {
         self.image_luts
             .push(ImageLut::new(self.device.as_ref(), Box::new(computer)));
@@ -605,7 +697,7 @@ impl WorldRenderer {
         &mut self,
         mesh: &'static PackedTriMesh::Flat,
         opts: AddMeshOptions,
-    ) -> MeshHandle {
+    ) -> anyhow::Result<MeshHandle> {
         let mesh_idx = self.meshes.len();
         let mut unique_images: Vec<AssetRef<GpuImage::Flat>> = mesh.maps.as_slice().to_vec();
         unique_images.sort();
@@ -618,6 +710,8 @@ impl WorldRenderer {
                     load_gpu_image_asset(device, asset)
                 })
                 .run()
+                .into_iter()
+                .collect::<anyhow::Result<Vec<_>>>()?
         };
         /*let loaded_images = {
             let device = self.device.clone();
@@ -772,10 +866,33 @@ impl WorldRenderer {
             lights: mesh_lights,
         });
 
-        MeshHandle(mesh_idx)
+        Ok(MeshHandle(mesh_idx))
+    }
+
+    /// Convention of camera matrices, instance transforms, the sun direction, and debug
+    /// drawing handed to the renderer; kajiya's own right-handed, Y-up one by default.
+    /// Instance transforms are converted as they're set, so set this before adding instances.
+    pub fn set_coordinate_system(&mut self, coordinate_system: CoordinateSystem) {
+        self.coordinate_system = coordinate_system;
+        self.debug_draw.coordinate_system = coordinate_system;
+    }
+
+    pub fn coordinate_system(&self) -> CoordinateSystem {
+        self.coordinate_system
+    }
+
+    // Meshes are loaded into kajiya's space, so their object space is converted too.
+    fn instance_transform_to_kajiya(&self, transform: Affine3A) -> Affine3A {
+        if self.coordinate_system.is_kajiya() {
+            return transform;
+        }
+
+        let to_kajiya = Affine3A::from_mat3(self.coordinate_system.to_kajiya());
+        to_kajiya * transform * to_kajiya.inverse()
     }
 
     pub fn add_instance(&mut self, mesh: MeshHandle, transform: Affine3A) -> InstanceHandle {
+        let transform = self.instance_transform_to_kajiya(transform);
         let handle = self.next_instance_handle;
         self.next_instance_handle += 1;
         let handle = InstanceHandle(handle);
@@ -814,7 +931,7 @@ impl WorldRenderer {
 
     pub fn set_instance_transform(&mut self, inst: InstanceHandle, transform: Affine3A) {
         let index = self.instance_handle_to_index[&inst];
-        self.instances[index].transform = transform;
+        self.instances[index].transform = self.instance_transform_to_kajiya(transform);
     }
 
     pub fn get_instance_dynamic_parameters(
@@ -862,6 +979,40 @@ impl WorldRenderer {
         self.frame_idx = 0;
     }
 
+    /// Sets the individual quality knobs of the renderer and its passes to the values
+    /// bundled in `preset`. They can still be tweaked individually afterwards.
+    pub fn apply_quality_preset(&mut self, preset: QualityPreset) {
+        let (spatial_reuse_pass_count, raytraced_reservoir_visibility, reuse_rtdgi_rays) =
+            match preset {
+                QualityPreset::Low => (1, false, true),
+                QualityPreset::Medium => (2, false, true),
+                QualityPreset::High => (2, true, false),
+                QualityPreset::Ultra => (3, true, false),
+            };
+
+        self.rtdgi.spatial_reuse_pass_count = spatial_reuse_pass_count;
+        self.rtdgi.use_raytraced_reservoir_visibility = raytraced_reservoir_visibility;
+
+        // Separate reflection rays are only worth it when GI visibility is traced too.
+        self.rtr.reuse_rtdgi_rays = reuse_rtdgi_rays;
+
+        self.use_motion_blur = preset != QualityPreset::Low;
+    }
+
+    /// Freezes the rendered image. While paused, the last frame is shown again instead of
+    /// running the world passes, so `frame_idx` and temporal history (TAA, denoisers,
+    /// exposure) hold still. The window, UI, and camera input keep working.
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused != self.paused {
+            self.paused = paused;
+            self.paused_frame = PausedFrame::None;
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     pub(super) fn prepare_top_level_acceleration(
         &mut self,
         rg: &mut rg::TemporalRenderGraph,
@@ -951,10 +1102,48 @@ impl WorldRenderer {
         self.exposure_state[self.render_mode as usize]
     }
 
+    /// Internal render resolution of the most recently prepared frame, before any upsampling.
+    pub fn render_dims(&self) -> [u32; 2] {
+        self.render_extent
+    }
+
     pub fn prepare_render_graph(
         &mut self,
         rg: &mut rg::TemporalRenderGraph,
         frame_desc: &WorldFrameDesc,
+    ) -> rg::Handle<Image> {
+        self.render_extent = frame_desc.render_extent;
+
+        if !self.paused {
+            return self.prepare_render_graph_impl(rg, frame_desc);
+        }
+
+        if let PausedFrame::Captured(desc) = self.paused_frame {
+            return Self::get_paused_frame(rg, desc);
+        }
+
+        // Render one more frame, and keep a copy of it to show while paused.
+        let output = self.prepare_render_graph_impl(rg, frame_desc);
+        let desc = *output.desc();
+        let mut frame = Self::get_paused_frame(rg, desc);
+        rg::imageops::blit_image(rg, &output, &mut frame);
+        self.paused_frame = PausedFrame::Capturing(desc);
+
+        output
+    }
+
+    fn get_paused_frame(rg: &mut rg::TemporalRenderGraph, desc: ImageDesc) -> rg::Handle<Image> {
+        rg.get_or_create_temporal(
+            "root.paused_frame",
+            desc.usage(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST),
+        )
+        .unwrap()
+    }
+
+    fn prepare_render_graph_impl(
+        &mut self,
+        rg: &mut rg::TemporalRenderGraph,
+        frame_desc: &WorldFrameDesc,
     ) -> rg::Handle<Image> {
         self.update_pre_exposure();
 
@@ -1010,6 +1199,7 @@ impl WorldRenderer {
                 .unwrap_or(frame_desc.camera_matrices),
             frame_desc.render_extent,
         )
+        .coordinate_system(self.coordinate_system)
         .build();
 
         // Re-shuffle the jitter sequence if we've just used it up
@@ -1070,14 +1260,28 @@ impl WorldRenderer {
 
         let real_sun_angular_radius = 0.53f32.to_radians() * 0.5;
 
-        let globals_offset = dynamic_constants.push(&FrameConstants {
+        let (sun_direction, sun_color_multiplier) = match &self.time_of_day {
+            Some(time_of_day) => {
+                let sun = time_of_day.sun();
+                (
+                    sun.direction,
+                    self.sun_color_multiplier * sun.color_multiplier,
+                )
+            }
+            None => (
+                self.coordinate_system.to_kajiya() * frame_desc.sun_direction,
+                self.sun_color_multiplier,
+            ),
+        };
+
+        let frame_constants = FrameConstants {
             view_constants,
-            sun_direction: frame_desc.sun_direction.extend(0.0),
+            sun_direction: sun_direction.extend(0.0),
             frame_index: self.frame_idx,
             delta_time_seconds,
             sun_angular_radius_cos: (self.sun_size_multiplier * real_sun_angular_radius).cos(),
 
-            sun_color_multiplier: self.sun_color_multiplier.extend(0.0),
+            sun_color_multiplier: sun_color_multiplier.extend(0.0),
             sky_ambient: self.sky_ambient.extend(0.0),
             triangle_light_count: triangle_lights.len() as _,
 
@@ -1090,7 +1294,28 @@ impl WorldRenderer {
 
             ircache_grid_center: self.ircache.grid_center().extend(1.0),
             ircache_cascades,
-        });
+        };
+
+        let globals_offset = dynamic_constants.push(&frame_constants);
+
+        // Additional views share everything but the view constants. They're not jittered,
+        // and don't track motion between frames.
+        let view_globals_offsets = self
+            .additional_views
+            .iter()
+            .map(|camera_matrices| {
+                dynamic_constants.push(&FrameConstants {
+                    view_constants: ViewConstants::builder(
+                        *camera_matrices,
+                        *camera_matrices,
+                        frame_desc.render_extent,
+                    )
+                    .coordinate_system(self.coordinate_system)
+                    .build(),
+                    ..frame_constants
+                })
+            })
+            .collect();
 
         let instance_dynamic_parameters_offset = dynamic_constants
             .push_from_iter(self.instances.iter().map(|inst| inst.dynamic_parameters));
@@ -1104,12 +1329,35 @@ impl WorldRenderer {
             globals_offset,
             instance_dynamic_parameters_offset,
             triangle_lights_offset,
+            view_globals_offsets,
         }
     }
 
+    /// Drives the sun from the given local solar time in hours, keeping the latitude and
+    /// season of the current `time_of_day`, or the defaults if there's none.
+    pub fn set_time_of_day(&mut self, hours: f32) {
+        self.time_of_day = Some(TimeOfDay {
+            hours,
+            ..self.time_of_day.unwrap_or_default()
+        });
+    }
+
     pub fn retire_frame(&mut self) {
+        match self.paused_frame {
+            PausedFrame::Captured(_) => {
+                // Nothing was rendered; keep the frame index and mesh history where they were.
+                self.debug_draw.clear();
+                return;
+            }
+            PausedFrame::Capturing(desc) => self.paused_frame = PausedFrame::Captured(desc),
+            PausedFrame::None => {}
+        }
+
         self.frame_idx = self.frame_idx.overflowing_add(1).0;
         self.store_prev_mesh_transforms();
+
+        // Not all render modes consume debug draws
+        self.debug_draw.clear();
     }
 }
 
@@ -1,6 +1,16 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+
 use kajiya_asset::mesh::PackedTriMesh;
+use kajiya_backend::canonical_path_from_vfs;
 
-use crate::world_renderer::{AddMeshOptions, MeshHandle, WorldRenderer};
+use crate::{
+    coordinate_system::CoordinateSystem,
+    world_renderer::{AddMeshOptions, MeshHandle, WorldRenderer},
+};
 
 impl WorldRenderer {
     pub fn add_baked_mesh(
@@ -8,9 +18,44 @@ impl WorldRenderer {
         path: impl Into<std::path::PathBuf>,
         opts: AddMeshOptions,
     ) -> anyhow::Result<MeshHandle> {
-        Ok(self.add_mesh(
+        self.add_mesh(
             crate::mmap::mmapped_asset::<PackedTriMesh::Flat, _>(path)?,
             opts,
-        ))
+        )
+    }
+
+    /// Loads a glTF scene, baking it into `/cache` first unless it's already there.
+    /// All primitives of all meshes in the scene are merged into one mesh.
+    ///
+    /// `coordinate_system` is the convention the scene is authored in, which is usually
+    /// the default glTF one regardless of `WorldRenderer::coordinate_system`.
+    pub fn load_gltf(
+        &mut self,
+        path: impl Into<PathBuf>,
+        coordinate_system: CoordinateSystem,
+        opts: AddMeshOptions,
+    ) -> anyhow::Result<MeshHandle> {
+        let path = path.into();
+
+        let path_hash = {
+            let mut s = DefaultHasher::new();
+            path.canonicalize().as_ref().unwrap_or(&path).hash(&mut s);
+            coordinate_system.hash(&mut s);
+            s.finish()
+        };
+
+        let cached_mesh_name = format!("{:8.8x}", path_hash);
+        let cached_mesh_path = PathBuf::from(format!("/cache/{}.mesh", cached_mesh_name));
+
+        if !canonical_path_from_vfs(&cached_mesh_path).map_or(false, |path| path.exists()) {
+            kajiya_asset_pipe::process_mesh_asset(kajiya_asset_pipe::MeshAssetProcessParams {
+                path,
+                output_name: cached_mesh_name,
+                scale: 1.0,
+                coordinate_system,
+            })?;
+        }
+
+        self.add_baked_mesh(cached_mesh_path, opts)
     }
 }
@@ -0,0 +1,79 @@
+use crate::camera::CameraMatrices;
+use macaw::{Mat3, Mat4, Vec3};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Handedness {
+    Right,
+    Left,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum UpAxis {
+    Y,
+    Z,
+}
+
+/// Convention of world-space data handed to kajiya: camera matrices, instance transforms,
+/// the sun direction, debug drawing, and mesh vertices.
+///
+/// kajiya works in a right-handed, Y-up space internally, with cameras looking down -Z;
+/// that is also the default here, and matches glTF. Data in other conventions is converted
+/// into kajiya's space at those entry points, so shaders don't need to know about it.
+///
+/// In view space, right-handed conventions look down -Z, and left-handed ones down +Z,
+/// both with +Y up.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CoordinateSystem {
+    pub handedness: Handedness,
+    pub up: UpAxis,
+}
+
+impl Default for CoordinateSystem {
+    fn default() -> Self {
+        Self::KAJIYA
+    }
+}
+
+impl CoordinateSystem {
+    pub const KAJIYA: Self = Self {
+        handedness: Handedness::Right,
+        up: UpAxis::Y,
+    };
+
+    /// Rotation or reflection from this coordinate system to kajiya's. It's orthonormal,
+    /// so its transpose converts back.
+    pub fn to_kajiya(&self) -> Mat3 {
+        match (self.handedness, self.up) {
+            (Handedness::Right, UpAxis::Y) => Mat3::IDENTITY,
+            (Handedness::Left, UpAxis::Y) => Mat3::from_cols(Vec3::X, Vec3::Y, -Vec3::Z),
+            (Handedness::Right, UpAxis::Z) => Mat3::from_cols(Vec3::X, -Vec3::Z, Vec3::Y),
+            (Handedness::Left, UpAxis::Z) => Mat3::from_cols(Vec3::X, Vec3::Z, Vec3::Y),
+        }
+    }
+
+    pub fn is_kajiya(&self) -> bool {
+        *self == Self::KAJIYA
+    }
+
+    /// Converts camera matrices whose world and view spaces follow this convention.
+    /// The projection is kept as-is.
+    pub fn camera_matrices_to_kajiya(&self, camera_matrices: CameraMatrices) -> CameraMatrices {
+        if self.is_kajiya() {
+            return camera_matrices;
+        }
+
+        let world = Mat4::from_mat3(self.to_kajiya());
+
+        // Maps kajiya's view space to this one's. Its own inverse.
+        let view = match self.handedness {
+            Handedness::Right => Mat4::IDENTITY,
+            Handedness::Left => Mat4::from_scale(Vec3::new(1.0, 1.0, -1.0)),
+        };
+
+        CameraMatrices {
+            view_to_world: world * camera_matrices.view_to_world * view,
+            world_to_view: view * camera_matrices.world_to_view * world.transpose(),
+            ..camera_matrices
+        }
+    }
+}
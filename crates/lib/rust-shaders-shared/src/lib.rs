@@ -1,6 +1,7 @@
 #![cfg_attr(target_arch = "spirv", no_std)]
 
 pub mod camera;
+pub mod coordinate_system;
 pub mod frame_constants;
 pub mod gbuffer;
 pub mod mesh;
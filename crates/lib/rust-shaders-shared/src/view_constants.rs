@@ -1,5 +1,7 @@
-use crate::camera::CameraMatrices;
-use macaw::{Mat4, UVec2, Vec2, Vec3};
+use crate::{camera::CameraMatrices, coordinate_system::CoordinateSystem};
+use macaw::{Mat4, UVec2, Vec2, Vec3, Vec4};
+#[cfg(target_arch = "spirv")]
+use spirv_std::num_traits::Float;
 
 #[derive(Clone, Copy)]
 #[repr(C, align(16))]
@@ -33,6 +35,10 @@ impl ViewConstants {
             camera_matrices: camera_matrices.into(),
             prev_camera_matrices: prev_camera_matrices.into(),
             pixel_offset: Vec2::ZERO,
+            coordinate_system: CoordinateSystem::KAJIYA,
+            vertical_fov_degrees: None,
+            near_plane_distance: None,
+            far_plane_distance: None,
         }
     }
 
@@ -80,6 +86,10 @@ pub struct VieportConstantBuilder {
     camera_matrices: CameraMatrices,
     prev_camera_matrices: CameraMatrices,
     pixel_offset: Vec2,
+    coordinate_system: CoordinateSystem,
+    vertical_fov_degrees: Option<f32>,
+    near_plane_distance: Option<f32>,
+    far_plane_distance: Option<f32>,
 }
 
 impl VieportConstantBuilder {
@@ -89,7 +99,75 @@ impl VieportConstantBuilder {
         self
     }
 
-    pub fn build(self) -> ViewConstants {
+    /// Convention of the camera matrices passed to the builder. They're converted to kajiya's
+    /// right-handed, Y-up space, which is also the default.
+    pub fn coordinate_system(mut self, v: CoordinateSystem) -> Self {
+        self.coordinate_system = v;
+        self
+    }
+
+    /// Override the vertical field of view of the camera matrices passed to the builder.
+    pub fn fov_degrees(mut self, v: f32) -> Self {
+        self.vertical_fov_degrees = Some(v);
+        self
+    }
+
+    /// Override the near plane distance of the camera matrices passed to the builder.
+    pub fn near(mut self, v: f32) -> Self {
+        self.near_plane_distance = Some(v);
+        self
+    }
+
+    /// Override the far plane distance of the camera matrices passed to the builder.
+    ///
+    /// `f32::INFINITY` results in an infinite far plane. Combined with reverse-Z, this retains
+    /// good depth precision at all distances without clipping far geometry.
+    pub fn far(mut self, v: f32) -> Self {
+        self.far_plane_distance = Some(v);
+        self
+    }
+
+    // Rebuilds the projection of `camera_matrices` with any overrides specified in the builder.
+    // Parameters which were not overridden are extracted from the original projection.
+    fn apply_projection_overrides(&self, camera_matrices: &mut CameraMatrices) {
+        if self.vertical_fov_degrees.is_none()
+            && self.near_plane_distance.is_none()
+            && self.far_plane_distance.is_none()
+        {
+            return;
+        }
+
+        let projection = ReverseZProjection::from_view_to_clip(camera_matrices.view_to_clip);
+        let projection = ReverseZProjection {
+            vertical_fov: self
+                .vertical_fov_degrees
+                .map_or(projection.vertical_fov, f32::to_radians),
+            near_plane_distance: self
+                .near_plane_distance
+                .unwrap_or(projection.near_plane_distance),
+            far_plane_distance: self
+                .far_plane_distance
+                .unwrap_or(projection.far_plane_distance),
+            ..projection
+        };
+
+        let (view_to_clip, clip_to_view) = projection.calc_matrices();
+        camera_matrices.view_to_clip = view_to_clip;
+        camera_matrices.clip_to_view = clip_to_view;
+    }
+
+    pub fn build(mut self) -> ViewConstants {
+        let mut camera_matrices = self
+            .coordinate_system
+            .camera_matrices_to_kajiya(self.camera_matrices);
+        let mut prev_camera_matrices = self
+            .coordinate_system
+            .camera_matrices_to_kajiya(self.prev_camera_matrices);
+        self.apply_projection_overrides(&mut camera_matrices);
+        self.apply_projection_overrides(&mut prev_camera_matrices);
+        self.camera_matrices = camera_matrices;
+        self.prev_camera_matrices = prev_camera_matrices;
+
         let clip_to_prev_clip = self.prev_camera_matrices.view_to_clip
             * self.prev_camera_matrices.world_to_view
             * self.camera_matrices.view_to_world
@@ -118,3 +196,63 @@ impl VieportConstantBuilder {
         res
     }
 }
+
+/// Perspective projection with reverse-Z, looking down the -Z axis in view space.
+///
+/// An infinite `far_plane_distance` results in an infinite far plane projection.
+#[derive(Clone, Copy)]
+struct ReverseZProjection {
+    vertical_fov: f32,
+    aspect_ratio: f32,
+    near_plane_distance: f32,
+    far_plane_distance: f32,
+}
+
+impl ReverseZProjection {
+    fn from_view_to_clip(view_to_clip: Mat4) -> Self {
+        let w = view_to_clip.x_axis.x;
+        let h = view_to_clip.y_axis.y;
+
+        // view_to_clip.z_axis.z = near / (far - near)
+        // view_to_clip.w_axis.z = near * far / (far - near)
+        let a = view_to_clip.z_axis.z;
+        let b = view_to_clip.w_axis.z;
+
+        Self {
+            vertical_fov: 2.0 * (1.0 / h).atan(),
+            aspect_ratio: h / w,
+            near_plane_distance: b / (1.0 + a),
+            far_plane_distance: if a != 0.0 { b / a } else { f32::INFINITY },
+        }
+    }
+
+    fn calc_matrices(&self) -> (Mat4, Mat4) {
+        let h = 1.0 / (0.5 * self.vertical_fov).tan();
+        let w = h / self.aspect_ratio;
+
+        let znear = self.near_plane_distance;
+        let zfar = self.far_plane_distance;
+
+        let (a, b) = if zfar == f32::INFINITY {
+            (0.0, znear)
+        } else {
+            (znear / (zfar - znear), znear * zfar / (zfar - znear))
+        };
+
+        let view_to_clip = Mat4::from_cols(
+            Vec4::new(w, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, h, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, a, -1.0),
+            Vec4::new(0.0, 0.0, b, 0.0),
+        );
+
+        let clip_to_view = Mat4::from_cols(
+            Vec4::new(1.0 / w, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 1.0 / h, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0 / b),
+            Vec4::new(0.0, 0.0, -1.0, a / b),
+        );
+
+        (view_to_clip, clip_to_view)
+    }
+}